@@ -0,0 +1,155 @@
+use proc_macro2::TokenTree;
+use quote::ToTokens;
+use syn::meta::ParseNestedMeta;
+use syn::{Expr, Field, Ident, LitStr, Token};
+
+const BUILDER_KEYS: &[&str] = &["each", "fixture", "flag", "setter"];
+const EACH_KEYS: &[&str] = &["name", "into"];
+const SETTER_KEYS: &[&str] = &["wrap"];
+
+// Either `each = "arg"` or `each(name = "arg", into)`.
+pub struct Each {
+    pub name: Ident,
+    pub into: bool,
+}
+
+#[derive(Default)]
+pub struct Attrs {
+    pub each: Option<Each>,
+    pub fixture: Option<Expr>,
+    pub flag: bool,
+    pub wrap: bool,
+}
+
+// Parses every #[builder(...)] attribute on the field. Parsing carries on past
+// unrecognized keys so that all mistakes in the field are reported together.
+pub fn parse(field: &Field) -> syn::Result<Attrs> {
+    let mut attrs = Attrs::default();
+    let mut errors = Vec::new();
+
+    for attr in &field.attrs {
+        if !attr.path().is_ident("builder") {
+            continue;
+        }
+        let result = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("each") {
+                attrs.each = parse_each(&meta, &mut errors)?;
+            } else if meta.path.is_ident("fixture") {
+                let lit: LitStr = meta.value()?.parse()?;
+                attrs.fixture = Some(lit.parse()?);
+            } else if meta.path.is_ident("flag") {
+                attrs.flag = true;
+            } else if meta.path.is_ident("setter") {
+                meta.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("wrap") {
+                        attrs.wrap = true;
+                    } else {
+                        errors.push(unrecognized(&meta, "setter", SETTER_KEYS));
+                        skip_value(&meta)?;
+                    }
+                    Ok(())
+                })?;
+            } else {
+                errors.push(unrecognized(&meta, "builder", BUILDER_KEYS));
+                skip_value(&meta)?;
+            }
+            Ok(())
+        });
+        if let Err(err) = result {
+            errors.push(err);
+        }
+    }
+
+    match crate::combine(errors) {
+        Some(err) => Err(err),
+        None => Ok(attrs),
+    }
+}
+
+fn parse_each(meta: &ParseNestedMeta, errors: &mut Vec<syn::Error>) -> syn::Result<Option<Each>> {
+    if meta.input.peek(Token![=]) {
+        let lit: LitStr = meta.value()?.parse()?;
+        return Ok(Some(Each {
+            name: lit.parse()?,
+            into: false,
+        }));
+    }
+
+    let mut name = None;
+    let mut into = false;
+    meta.parse_nested_meta(|meta| {
+        if meta.path.is_ident("name") {
+            let lit: LitStr = meta.value()?.parse()?;
+            name = Some(lit.parse()?);
+        } else if meta.path.is_ident("into") {
+            into = true;
+        } else {
+            errors.push(unrecognized(&meta, "each", EACH_KEYS));
+            skip_value(&meta)?;
+        }
+        Ok(())
+    })?;
+
+    match name {
+        Some(name) => Ok(Some(Each { name, into })),
+        None => {
+            errors.push(meta.error("missing `name = \"...\"` in `each(...)`"));
+            Ok(None)
+        }
+    }
+}
+
+fn unrecognized(meta: &ParseNestedMeta, parent: &str, expected: &[&str]) -> syn::Error {
+    let key = meta.path.to_token_stream().to_string().replace(' ', "");
+    let mut message = format!("unrecognized attribute `{}({})`", parent, key);
+    if let Some(suggestion) = closest(&key, expected) {
+        message += &format!(", did you mean `{}`?", suggestion);
+    }
+    let expected: Vec<String> = expected.iter().map(|key| format!("`{}`", key)).collect();
+    message += &format!(" (supported: {})", expected.join(", "));
+    syn::Error::new_spanned(&meta.path, message)
+}
+
+// Consumes whatever follows an unrecognized key, either `= value` or a
+// parenthesized list, so that parsing can resume at the next key.
+fn skip_value(meta: &ParseNestedMeta) -> syn::Result<()> {
+    if meta.input.peek(Token![=]) {
+        meta.input.parse::<Token![=]>()?;
+        while !meta.input.is_empty() && !meta.input.peek(Token![,]) {
+            meta.input.parse::<TokenTree>()?;
+        }
+    } else if !meta.input.is_empty() && !meta.input.peek(Token![,]) {
+        meta.input.parse::<TokenTree>()?;
+    }
+    Ok(())
+}
+
+fn closest<'a>(key: &str, candidates: &[&'a str]) -> Option<&'a str> {
+    let limit = key.len().max(3).div_ceil(2);
+    candidates
+        .iter()
+        .map(|candidate| (edit_distance(key, candidate), *candidate))
+        .filter(|(distance, _)| *distance <= limit)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+// Levenshtein distance.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cur = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                prev
+            } else {
+                1 + prev.min(row[j]).min(cur)
+            };
+            prev = cur;
+        }
+    }
+    row[b.len()]
+}
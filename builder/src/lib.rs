@@ -1,10 +1,545 @@
 extern crate proc_macro;
 
+mod attr;
+
+use crate::attr::Each;
 use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2, TokenTree};
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, Data, DeriveInput, Expr, Field, Fields, GenericArgument, Generics, Ident,
+    PathArguments, Type,
+};
 
-#[proc_macro_derive(Builder)]
+#[proc_macro_derive(Builder, attributes(builder))]
 pub fn derive(input: TokenStream) -> TokenStream {
-    let _ = input;
+    let input = parse_macro_input!(input as DeriveInput);
+
+    expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new(
+                    Span::call_site(),
+                    "Builder can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                "Builder can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut errors = Vec::new();
+    let fields: Vec<_> = fields
+        .iter()
+        .filter_map(|field| {
+            BuilderField::parse(field)
+                .map_err(|err| errors.push(err))
+                .ok()
+        })
+        .collect();
+    if let Some(err) = combine(errors) {
+        return Err(err);
+    }
+
+    let vis = &input.vis;
+    let name = &input.ident;
+    let builder = format_ident!("{}Builder", name);
+    let generics = &input.generics;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let storage = fields.iter().map(BuilderField::storage);
+    let init = fields.iter().map(BuilderField::init);
+    let fixture_builder = fixture_builder(&builder, generics, &fields);
+    let setters = fields.iter().map(BuilderField::setters);
+    let check = check_required(&fields);
+    let build = fields.iter().map(BuilderField::build);
+
+    Ok(quote! {
+        #vis struct #builder #generics #where_clause {
+            #(#storage,)*
+        }
+
+        impl #impl_generics #name #ty_generics #where_clause {
+            pub fn builder() -> #builder #ty_generics {
+                #builder {
+                    #(#init,)*
+                }
+            }
+
+            #fixture_builder
+        }
+
+        impl #impl_generics #builder #ty_generics #where_clause {
+            #(#setters)*
+
+            pub fn build(
+                &mut self,
+            ) -> ::std::result::Result<
+                #name #ty_generics,
+                ::std::boxed::Box<dyn ::std::error::Error>,
+            > {
+                #check
+                ::std::result::Result::Ok(#name {
+                    #(#build,)*
+                })
+            }
+        }
+    })
+}
+
+// Reports every unset required field in one error. The per-field cost is a
+// single `is_some()` and a string literal, so that structs with hundreds of
+// fields don't pay for a closure and error construction on each of them.
+fn check_required(fields: &[BuilderField]) -> TokenStream2 {
+    let required: Vec<&Ident> = fields
+        .iter()
+        .filter(|field| matches!(field.kind, FieldKind::Required(_)))
+        .map(|field| field.ident)
+        .collect();
+    if required.is_empty() {
+        return TokenStream2::new();
+    }
+
+    let names = required.iter().map(|ident| format!("`{}`", ident));
+    quote! {
+        let set = [#(self.#required.is_some()),*];
+        if set.contains(&false) {
+            let names = [#(#names),*];
+            let mut missing = ::std::string::String::new();
+            for (name, set) in names.iter().zip(set) {
+                if !set {
+                    if !missing.is_empty() {
+                        missing.push_str(", ");
+                    }
+                    missing.push_str(name);
+                }
+            }
+            return ::std::result::Result::Err(::std::convert::From::from(
+                ::std::format!("missing required fields: {}", missing),
+            ));
+        }
+    }
+}
+
+// Generated only when some field declares #[builder(fixture = "...")], since
+// required fields without a fixture fall back to Default, which not every
+// field type implements. Where such a field's type depends on a type
+// parameter, the method requires it to be Default, so that it only exists for
+// the instantiations it can build; other types must be Default outright.
+fn fixture_builder(builder: &Ident, generics: &Generics, fields: &[BuilderField]) -> TokenStream2 {
+    if fields.iter().all(|field| field.fixture.is_none()) {
+        return TokenStream2::new();
+    }
+
+    let (_, ty_generics, _) = generics.split_for_impl();
+    let params: Vec<&Ident> = generics.type_params().map(|param| &param.ident).collect();
+    let defaulted = fields
+        .iter()
+        .filter(|field| matches!(field.kind, FieldKind::Required(_)) && field.fixture.is_none())
+        .map(|field| field.ty)
+        .filter(|ty| mentions(quote!(#ty), &params));
+    let fixture = fields.iter().map(BuilderField::fixture);
+    quote! {
+        pub fn fixture_builder() -> #builder #ty_generics
+        where
+            #(#defaulted: ::std::default::Default,)*
+        {
+            #builder {
+                #(#fixture,)*
+            }
+        }
+    }
+}
+
+// Whether `tokens` mention any of `params`.
+fn mentions(tokens: TokenStream2, params: &[&Ident]) -> bool {
+    tokens.into_iter().any(|token| match token {
+        TokenTree::Ident(ident) => params.iter().any(|param| **param == ident),
+        TokenTree::Group(group) => mentions(group.stream(), params),
+        _ => false,
+    })
+}
+
+struct BuilderField<'a> {
+    ident: &'a Ident,
+    ty: &'a Type,
+    kind: FieldKind<'a>,
+    fixture: Option<Expr>,
+}
+
+enum FieldKind<'a> {
+    // Must be set before calling build().
+    Required(Setter<'a>),
+    // Field of type Option<T>; the setter accepts T.
+    Optional(Setter<'a>),
+    // Field of type Vec<T> with #[builder(each = "...")].
+    Repeated { each: Each, elem: &'a Type },
+    // Field of type bool with #[builder(flag)].
+    Flag,
+}
+
+// The argument accepted by a single-value setter, and how to turn it into the
+// value that gets stored.
+struct Setter<'a> {
+    ty: &'a Type,
+    wrap: Option<Wrapper>,
+}
+
+#[derive(Clone, Copy)]
+enum Wrapper {
+    Box,
+    Arc,
+    Rc,
+    Cow,
+}
+
+impl<'a> BuilderField<'a> {
+    fn parse(field: &'a Field) -> syn::Result<Self> {
+        let ident = field.ident.as_ref().unwrap();
+        let ty = &field.ty;
+        let mut attrs = attr::parse(field)?;
+        let fixture = attrs.fixture.take();
+
+        let kind = if attrs.flag {
+            if attrs.each.is_some() || attrs.wrap {
+                return Err(syn::Error::new_spanned(
+                    ident,
+                    "`flag` cannot be combined with other builder attributes",
+                ));
+            }
+            if !is_bool(ty) {
+                return Err(syn::Error::new_spanned(
+                    ty,
+                    "`builder(flag)` requires a field of type `bool`",
+                ));
+            }
+            FieldKind::Flag
+        } else if let Some(each) = attrs.each {
+            if attrs.wrap {
+                return Err(syn::Error::new_spanned(
+                    ident,
+                    "`setter(wrap)` cannot be combined with `each`",
+                ));
+            }
+            let elem = match generic_argument(ty, "Vec") {
+                Some(elem) => elem,
+                None => {
+                    return Err(syn::Error::new_spanned(
+                        ty,
+                        "`builder(each = \"...\")` requires a field of type `Vec<T>`",
+                    ))
+                }
+            };
+            FieldKind::Repeated { each, elem }
+        } else if let Some(inner) = generic_argument(ty, "Option") {
+            FieldKind::Optional(Setter::new(inner, attrs.wrap)?)
+        } else {
+            FieldKind::Required(Setter::new(ty, attrs.wrap)?)
+        };
+
+        Ok(BuilderField {
+            ident,
+            ty,
+            kind,
+            fixture,
+        })
+    }
+
+    fn storage(&self) -> TokenStream2 {
+        let ident = self.ident;
+        match &self.kind {
+            FieldKind::Required(setter) | FieldKind::Optional(setter) => {
+                let ty = setter.ty;
+                quote!(#ident: ::std::option::Option<#ty>)
+            }
+            FieldKind::Repeated { .. } => {
+                let ty = self.ty;
+                quote!(#ident: #ty)
+            }
+            FieldKind::Flag => quote!(#ident: bool),
+        }
+    }
+
+    fn init(&self) -> TokenStream2 {
+        let ident = self.ident;
+        match &self.kind {
+            FieldKind::Required(_) | FieldKind::Optional(_) => {
+                quote!(#ident: ::std::option::Option::None)
+            }
+            FieldKind::Repeated { .. } => quote!(#ident: ::std::vec::Vec::new()),
+            FieldKind::Flag => quote!(#ident: false),
+        }
+    }
+
+    // Initial value in fixture_builder(): the field's fixture if it has one,
+    // otherwise Default for required fields and empty for the rest.
+    fn fixture(&self) -> TokenStream2 {
+        let ident = self.ident;
+        match (&self.kind, &self.fixture) {
+            (FieldKind::Required(_), Some(fixture)) => {
+                quote!(#ident: ::std::option::Option::Some(#fixture))
+            }
+            (FieldKind::Required(_), None) => {
+                quote!(#ident: ::std::option::Option::Some(::std::default::Default::default()))
+            }
+            (_, Some(fixture)) => quote!(#ident: #fixture),
+            (_, None) => self.init(),
+        }
+    }
+
+    fn setters(&self) -> TokenStream2 {
+        let ident = self.ident;
+        match &self.kind {
+            FieldKind::Required(setter) | FieldKind::Optional(setter) => {
+                let arg = setter.arg();
+                let value = setter.value(ident);
+                quote! {
+                    pub fn #ident(&mut self, #ident: #arg) -> &mut Self {
+                        self.#ident = ::std::option::Option::Some(#value);
+                        self
+                    }
+                }
+            }
+            FieldKind::Repeated { each, elem } => {
+                let ty = self.ty;
+                let name = &each.name;
+                let extend = format_ident!("{}_extend", ident);
+                let clear = format_ident!("{}_clear", ident);
+                let all = if name == ident {
+                    None
+                } else {
+                    Some(quote! {
+                        pub fn #ident(&mut self, #ident: #ty) -> &mut Self {
+                            self.#ident = #ident;
+                            self
+                        }
+                    })
+                };
+                let (item, extend_body) = if each.into {
+                    (
+                        quote!(impl ::std::convert::Into<#elem>),
+                        quote! {
+                            self.#ident.extend(
+                                ::std::iter::IntoIterator::into_iter(#ident)
+                                    .map(::std::convert::Into::into),
+                            )
+                        },
+                    )
+                } else {
+                    (quote!(#elem), quote!(self.#ident.extend(#ident)))
+                };
+                let push = if each.into {
+                    quote!(::std::convert::Into::into(#name))
+                } else {
+                    quote!(#name)
+                };
+                quote! {
+                    pub fn #name(&mut self, #name: #item) -> &mut Self {
+                        self.#ident.push(#push);
+                        self
+                    }
+
+                    pub fn #extend(
+                        &mut self,
+                        #ident: impl ::std::iter::IntoIterator<Item = #item>,
+                    ) -> &mut Self {
+                        #extend_body;
+                        self
+                    }
+
+                    pub fn #clear(&mut self) -> &mut Self {
+                        self.#ident.clear();
+                        self
+                    }
+
+                    #all
+                }
+            }
+            FieldKind::Flag => {
+                let ident_if = format_ident!("{}_if", ident);
+                quote! {
+                    pub fn #ident(&mut self) -> &mut Self {
+                        self.#ident = true;
+                        self
+                    }
+
+                    pub fn #ident_if(&mut self, #ident: bool) -> &mut Self {
+                        self.#ident = #ident;
+                        self
+                    }
+                }
+            }
+        }
+    }
+
+    fn build(&self) -> TokenStream2 {
+        let ident = self.ident;
+        match &self.kind {
+            // Presence was already checked by check_required.
+            FieldKind::Required(_) => quote!(#ident: self.#ident.take().unwrap()),
+            FieldKind::Optional(_) => quote!(#ident: self.#ident.take()),
+            FieldKind::Repeated { .. } => quote!(#ident: ::std::mem::take(&mut self.#ident)),
+            FieldKind::Flag => quote!(#ident: self.#ident),
+        }
+    }
+}
+
+impl<'a> Setter<'a> {
+    fn new(ty: &'a Type, wrap: bool) -> syn::Result<Self> {
+        if !wrap {
+            return Ok(Setter { ty, wrap: None });
+        }
+        match Wrapper::of(ty) {
+            Some(wrapper) => Ok(Setter {
+                ty,
+                wrap: Some(wrapper),
+            }),
+            None => Err(syn::Error::new_spanned(
+                ty,
+                "`setter(wrap)` requires a field of type `Box<T>`, `Arc<T>`, `Rc<T>` or `Cow<'_, T>`",
+            )),
+        }
+    }
+
+    // Type of the setter's argument.
+    fn arg(&self) -> TokenStream2 {
+        let ty = self.ty;
+        match self.wrap {
+            None => quote!(#ty),
+            Some(Wrapper::Cow) => {
+                let inner = generic_argument(ty, "Cow").unwrap();
+                quote!(<#inner as ::std::borrow::ToOwned>::Owned)
+            }
+            Some(wrapper) => {
+                let inner = generic_argument(ty, wrapper.name()).unwrap();
+                quote!(#inner)
+            }
+        }
+    }
+
+    // Expression converting the setter's argument into the stored value.
+    fn value(&self, arg: &Ident) -> TokenStream2 {
+        match self.wrap {
+            None => quote!(#arg),
+            Some(Wrapper::Box) => quote!(::std::boxed::Box::new(#arg)),
+            Some(Wrapper::Arc) => quote!(::std::sync::Arc::new(#arg)),
+            Some(Wrapper::Rc) => quote!(::std::rc::Rc::new(#arg)),
+            Some(Wrapper::Cow) => quote!(::std::borrow::Cow::Owned(#arg)),
+        }
+    }
+}
+
+impl Wrapper {
+    fn of(ty: &Type) -> Option<Self> {
+        [Wrapper::Box, Wrapper::Arc, Wrapper::Rc, Wrapper::Cow]
+            .iter()
+            .copied()
+            .find(|wrapper| generic_argument(ty, wrapper.name()).is_some())
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Wrapper::Box => "Box",
+            Wrapper::Arc => "Arc",
+            Wrapper::Rc => "Rc",
+            Wrapper::Cow => "Cow",
+        }
+    }
+}
+
+fn is_bool(ty: &Type) -> bool {
+    match ty {
+        Type::Path(ty) => ty.qself.is_none() && ty.path.is_ident("bool"),
+        _ => false,
+    }
+}
+
+// If `ty` is written as `Wrapper<..., T>` (possibly behind a module path),
+// returns `T`. Lifetimes preceding the type argument, as in `Cow<'a, T>`, are
+// skipped.
+fn generic_argument<'a>(ty: &'a Type, wrapper: &str) -> Option<&'a Type> {
+    let path = match ty {
+        Type::Path(ty) if ty.qself.is_none() => &ty.path,
+        _ => return None,
+    };
+    let segment = path.segments.last()?;
+    if segment.ident != wrapper {
+        return None;
+    }
+    let args = match &segment.arguments {
+        PathArguments::AngleBracketed(args) => &args.args,
+        _ => return None,
+    };
+    let mut types = args.iter().filter_map(|arg| match arg {
+        GenericArgument::Type(ty) => Some(ty),
+        _ => None,
+    });
+    match (types.next(), types.next()) {
+        (Some(ty), None) => Some(ty),
+        _ => None,
+    }
+}
+
+fn combine(errors: impl IntoIterator<Item = syn::Error>) -> Option<syn::Error> {
+    errors.into_iter().reduce(|mut errors, err| {
+        errors.combine(err);
+        errors
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use proc_macro2::{TokenStream, TokenTree};
+    use quote::{format_ident, quote};
+
+    // Synthetic struct shaped like a generated protocol message: mostly
+    // required scalars with some optional and repeated fields mixed in.
+    fn synthetic(fields: usize) -> syn::DeriveInput {
+        let fields = (0..fields).map(|i| {
+            let ident = format_ident!("field_{}", i);
+            match i % 4 {
+                0 | 1 => quote!(#ident: u64),
+                2 => quote!(#ident: Option<String>),
+                _ => {
+                    let each = format_ident!("item_{}", i);
+                    let each = each.to_string();
+                    quote!(#[builder(each = #each)] #ident: Vec<u32>)
+                }
+            }
+        });
+        syn::parse_quote! {
+            pub struct Message {
+                #(#fields,)*
+            }
+        }
+    }
+
+    fn count(tokens: TokenStream) -> usize {
+        tokens
+            .into_iter()
+            .map(|token| match token {
+                TokenTree::Group(group) => 1 + count(group.stream()),
+                _ => 1,
+            })
+            .sum()
+    }
 
-    unimplemented!()
+    #[test]
+    fn expansion_size() {
+        let fields = 500;
+        let tokens = count(super::expand(&synthetic(fields)).unwrap());
+        let per_field = tokens / fields;
+        assert!(per_field <= 110, "{} tokens per field", per_field);
+    }
 }
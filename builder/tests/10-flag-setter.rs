@@ -0,0 +1,53 @@
+// Boolean fields are usually switches that callers either turn on or leave
+// alone. Writing `.verbose(true)` is noisy, so a field marked with
+// #[builder(flag)] gets a setter taking no argument that sets the field to
+// true, plus a `_if` variant for when the condition is only known at runtime.
+//
+//     impl CommandBuilder {
+//         pub fn verbose(&mut self) -> &mut Self {
+//             self.verbose = true;
+//             self
+//         }
+//
+//         pub fn verbose_if(&mut self, verbose: bool) -> &mut Self {
+//             self.verbose = verbose;
+//             self
+//         }
+//     }
+//
+// A flag that is never set defaults to false rather than making build() fail.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    #[builder(flag)]
+    verbose: bool,
+    #[builder(flag)]
+    dry_run: bool,
+    #[builder(flag)]
+    color: bool,
+}
+
+fn main() {
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .verbose()
+        .color_if(false)
+        .build()
+        .unwrap();
+
+    assert!(command.verbose);
+    assert!(!command.dry_run);
+    assert!(!command.color);
+
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .dry_run_if(1 + 1 == 2)
+        .build()
+        .unwrap();
+
+    assert!(!command.verbose);
+    assert!(command.dry_run);
+}
@@ -0,0 +1,53 @@
+// Fields holding a Box, Arc, Rc or Cow force every caller to wrap the value
+// themselves before handing it to the setter. With #[builder(setter(wrap))] the
+// setter accepts the inner value and does the wrapping.
+//
+//     impl CommandBuilder {
+//         pub fn env(&mut self, env: Vec<String>) -> &mut Self {
+//             self.env = Some(Arc::new(env));
+//             self
+//         }
+//     }
+//
+// For Cow<'a, T> the setter accepts the owned form of T, so a Cow<'a, str>
+// field takes a String.
+//
+// The attribute also applies to optional fields, where the wrapper is found
+// inside of the Option.
+
+use derive_builder::Builder;
+use std::borrow::Cow;
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::Arc;
+
+#[derive(Builder)]
+pub struct Command {
+    #[builder(setter(wrap))]
+    executable: Box<String>,
+    #[builder(setter(wrap))]
+    env: Arc<Vec<String>>,
+    #[builder(setter(wrap))]
+    stdin: Rc<[u8; 4]>,
+    #[builder(setter(wrap))]
+    name: Cow<'static, str>,
+    #[builder(setter(wrap))]
+    current_dir: Option<Cow<'static, Path>>,
+}
+
+fn main() {
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .env(vec!["RUST_LOG=debug".to_owned()])
+        .stdin(*b"ping")
+        .name("build".to_owned())
+        .current_dir("..".into())
+        .build()
+        .unwrap();
+
+    assert_eq!(*command.executable, "cargo");
+    assert_eq!(*command.env, ["RUST_LOG=debug"]);
+    assert_eq!(&*command.stdin, b"ping");
+    assert!(matches!(command.name, Cow::Owned(ref name) if name == "build"));
+    assert_eq!(command.current_dir.unwrap(), Path::new(".."));
+}
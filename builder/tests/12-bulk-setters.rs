@@ -0,0 +1,48 @@
+// Fields with #[builder(each = "...")] also get setters that operate on the
+// whole collection at once, so that an existing Vec or iterator can be passed
+// in without a loop:
+//
+//     impl CommandBuilder {
+//         pub fn args_extend(&mut self, args: impl IntoIterator<Item = String>) -> &mut Self;
+//         pub fn args_clear(&mut self) -> &mut Self;
+//     }
+//
+// The all-at-once setter named after the field is still only generated when
+// it doesn't collide with the one-at-a-time setter.
+//
+// The longer form #[builder(each(name = "...", into))] makes the one-at-a-time
+// and extend setters accept anything convertible into the element type.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    #[builder(each(name = "arg", into))]
+    args: Vec<String>,
+    #[builder(each = "env")]
+    env: Vec<String>,
+    #[builder(each = "path")]
+    paths: Vec<String>,
+}
+
+fn main() {
+    let release = vec!["--release".to_owned()];
+
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .arg("build")
+        .args_extend(release)
+        .args_extend(["--bin", "workshop"])
+        .env_extend(vec!["A=1".to_owned(), "B=2".to_owned()])
+        .env_clear()
+        .env("C=3".to_owned())
+        .path("/usr/bin".to_owned())
+        .paths(vec!["/bin".to_owned()])
+        .build()
+        .unwrap();
+
+    assert_eq!(command.args, ["build", "--release", "--bin", "workshop"]);
+    assert_eq!(command.env, ["C=3"]);
+    assert_eq!(command.paths, ["/bin"]);
+}
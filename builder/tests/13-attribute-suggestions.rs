@@ -0,0 +1,21 @@
+// A refactor can easily leave several typos behind in the builder attributes of
+// one struct. Rather than stopping at the first unrecognized key, the macro
+// reports every one of them in a single compile, each with the list of keys
+// that would have been accepted at that position and, when one of them is close
+// enough by edit distance, a suggestion of what was probably meant.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    #[builder(setter(wrp))]
+    executable: Box<String>,
+    #[builder(each(nme = "arg", into))]
+    args: Vec<String>,
+    #[builder(flg)]
+    verbose: bool,
+    #[builder(default = "..")]
+    current_dir: Option<String>,
+}
+
+fn main() {}
@@ -0,0 +1,31 @@
+// When build() is called with required fields still unset, the error lists all
+// of them at once rather than only the first one found, and the builder is
+// left untouched so the caller can fill in the gaps and try again.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    args: Vec<String>,
+    env: Vec<String>,
+    current_dir: Option<String>,
+}
+
+fn main() {
+    let mut builder = Command::builder();
+    builder.args(vec![]);
+
+    let err = builder.build().err().unwrap();
+    assert_eq!(
+        err.to_string(),
+        "missing required fields: `executable`, `env`",
+    );
+
+    let command = builder
+        .executable("cargo".to_owned())
+        .env(vec![])
+        .build()
+        .unwrap();
+    assert!(command.args.is_empty());
+}
@@ -0,0 +1,73 @@
+// Test suites tend to construct many values of the same struct while caring
+// about only one or two of its fields. Give fields a #[builder(fixture = "...")]
+// attribute holding an expression of the field's type, and generate
+//
+//     impl Command {
+//         pub fn fixture_builder() -> CommandBuilder {...}
+//     }
+//
+// which returns a builder with every field pre-filled from its fixture.
+// Required fields without a fixture are pre-filled with Default::default(), so
+// a new required field only needs a fixture if its type has no Default impl.
+// Fields without a fixture that are optional, repeated or flags start out the
+// same as in builder().
+//
+// The setters still work as usual on the returned builder, so tests override
+// only what they care about.
+//
+// On a generic struct, a required field without a fixture whose type depends
+// on a type parameter makes fixture_builder() available only where that type
+// is Default. The rest of the builder works for any type.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    #[builder(fixture = r#""cargo".to_owned()"#)]
+    executable: String,
+    #[builder(each = "arg", fixture = r#"vec!["build".to_owned()]"#)]
+    args: Vec<String>,
+    env: Vec<String>,
+    #[builder(fixture = r#"Some("/tmp".to_owned())"#)]
+    current_dir: Option<String>,
+    timeout: Option<u64>,
+    #[builder(flag, fixture = "true")]
+    verbose: bool,
+}
+
+#[derive(Builder)]
+pub struct Job<T> {
+    #[builder(fixture = "7")]
+    id: u32,
+    payload: T,
+}
+
+// Not Default.
+pub struct Payload(u8);
+
+fn main() {
+    let command = Command::fixture_builder().build().unwrap();
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, ["build"]);
+    assert!(command.env.is_empty());
+    assert_eq!(command.current_dir.as_deref(), Some("/tmp"));
+    assert_eq!(command.timeout, None);
+    assert!(command.verbose);
+
+    let command = Command::fixture_builder()
+        .arg("--release".to_owned())
+        .env(vec!["A=1".to_owned()])
+        .verbose_if(false)
+        .build()
+        .unwrap();
+    assert_eq!(command.args, ["build", "--release"]);
+    assert_eq!(command.env, ["A=1"]);
+    assert!(!command.verbose);
+
+    let job = Job::<Vec<u8>>::fixture_builder().build().unwrap();
+    assert_eq!(job.id, 7);
+    assert!(job.payload.is_empty());
+
+    let job = Job::builder().id(1).payload(Payload(2)).build().unwrap();
+    assert_eq!((job.id, job.payload.0), (1, 2));
+}
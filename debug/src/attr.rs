@@ -0,0 +1,466 @@
+use proc_macro2::TokenStream;
+use syn::meta::ParseNestedMeta;
+use syn::punctuated::Punctuated;
+use syn::{
+    parenthesized, token, Attribute, Expr, ExprLit, Ident, Lit, LitInt, LitStr, Meta, Path, Token,
+    WherePredicate,
+};
+
+#[derive(Default)]
+pub struct ContainerAttrs {
+    // #[debug(bound = "...")], replacing all inferred bounds.
+    pub bound: Option<Punctuated<WherePredicate, Token![,]>>,
+    // #[debug(transparent)]
+    pub transparent: bool,
+    // #[debug(deny_secrets)]
+    pub deny_secrets: bool,
+    // #[debug(sorted)], sorting every HashMap and HashSet field.
+    pub sorted: bool,
+    // #[debug(with = "...")], a function rendering the whole value.
+    pub with: Option<Path>,
+    // #[debug(extra(name = "...", expr = "..."))], in the order given.
+    pub extras: Vec<Extra>,
+    // #[debug(json)], generating `to_debug_json`.
+    pub json: Option<Ident>,
+    // #[debug(max_depth = N)], the number of nested values of this type to
+    // print before eliding the rest.
+    pub max_depth: Option<usize>,
+    // #[debug(opaque)], printing only the fields marked #[debug(show)].
+    pub opaque: Option<Ident>,
+}
+
+// A computed entry printed after the fields.
+pub struct Extra {
+    pub name: LitStr,
+    pub expr: Expr,
+}
+
+#[derive(Default)]
+pub struct FieldAttrs {
+    // #[debug = "..."]
+    pub format: Option<LitStr>,
+    // #[debug(skip)]
+    pub skip: bool,
+    // #[debug(skip_if = "...")], a predicate taking a reference to the field.
+    pub skip_if: Option<Path>,
+    // #[debug(rename = "...")]
+    pub rename: Option<LitStr>,
+    // #[debug(with = "...")], a function rendering the field in place of its
+    // Debug impl.
+    pub with: Option<Path>,
+    // #[debug(redact)] or #[debug(redact = "len" | "hash")]
+    pub redact: Option<Redact>,
+    // #[debug(bound = "...")], replacing the bounds inferred from this field.
+    pub bound: Option<Punctuated<WherePredicate, Token![,]>>,
+    // #[debug(truncate = N)], the number of elements or characters to print.
+    pub truncate: Option<usize>,
+    // #[debug(bytes)], printing the field as hex.
+    pub bytes: bool,
+    // #[debug(flatten)], printing the fields of a nested CustomDebug struct
+    // in place of the field itself.
+    pub flatten: bool,
+    // #[debug(flatten, prefix = "...")], prepended to the nested field names.
+    pub prefix: Option<LitStr>,
+    // #[debug(sorted)], printing a map or set in sorted order.
+    pub sorted: bool,
+    // #[debug(show)], printing the field of an opaque type.
+    pub show: Option<Ident>,
+}
+
+// #[eq(...)] or #[hash(...)] on a type deriving CustomPartialEq or CustomHash.
+#[derive(Default)]
+pub struct CmpContainerAttrs {
+    // bound = "...", replacing all inferred bounds.
+    pub bound: Option<Punctuated<WherePredicate, Token![,]>>,
+}
+
+// #[eq(...)] or #[hash(...)] on a field.
+#[derive(Default)]
+pub struct CmpFieldAttrs {
+    // skip, leaving the field out of the comparison or hash.
+    pub skip: bool,
+    // with = "...", a function comparing or hashing the field in place of
+    // its PartialEq or Hash impl.
+    pub with: Option<Path>,
+}
+
+// Field attributes of one of the derives.
+pub trait Attrs {
+    // Whether the field is left out entirely.
+    fn skip(&self) -> bool;
+}
+
+impl Attrs for FieldAttrs {
+    fn skip(&self) -> bool {
+        self.skip
+    }
+}
+
+impl Attrs for CmpFieldAttrs {
+    fn skip(&self) -> bool {
+        self.skip
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Redact {
+    Full,
+    Len,
+    Hash,
+}
+
+pub fn container(attrs: &[Attribute]) -> syn::Result<ContainerAttrs> {
+    let mut container = ContainerAttrs::default();
+
+    for attr in attrs {
+        if !attr.path().is_ident("debug") {
+            continue;
+        }
+        if let Meta::NameValue(_) = &attr.meta {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "#[debug = \"...\"] is only allowed on fields",
+            ));
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("bound") {
+                container.bound = Some(bound(&meta)?);
+                Ok(())
+            } else if meta.path.is_ident("transparent") {
+                container.transparent = true;
+                Ok(())
+            } else if meta.path.is_ident("deny_secrets") {
+                container.deny_secrets = true;
+                Ok(())
+            } else if meta.path.is_ident("sorted") {
+                container.sorted = true;
+                Ok(())
+            } else if meta.path.is_ident("with") {
+                container.with = Some(path(&meta)?);
+                Ok(())
+            } else if meta.path.is_ident("json") {
+                container.json = meta.path.get_ident().cloned();
+                Ok(())
+            } else if meta.path.is_ident("opaque") {
+                container.opaque = meta.path.get_ident().cloned();
+                Ok(())
+            } else if meta.path.is_ident("max_depth") {
+                let lit: LitInt = meta.value()?.parse()?;
+                let max_depth = lit.base10_parse()?;
+                if max_depth == 0 {
+                    return Err(syn::Error::new_spanned(
+                        lit,
+                        "`max_depth` must be at least 1",
+                    ));
+                }
+                container.max_depth = Some(max_depth);
+                Ok(())
+            } else if meta.path.is_ident("extra") {
+                let mut name = None;
+                let mut expr = None;
+                meta.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("name") {
+                        name = Some(meta.value()?.parse()?);
+                        Ok(())
+                    } else if meta.path.is_ident("expr") {
+                        let lit: LitStr = meta.value()?.parse()?;
+                        expr = Some(lit.parse()?);
+                        Ok(())
+                    } else {
+                        Err(meta.error("expected `name` or `expr`"))
+                    }
+                })?;
+                match (name, expr) {
+                    (Some(name), Some(expr)) => {
+                        container.extras.push(Extra { name, expr });
+                        Ok(())
+                    }
+                    _ => Err(meta.error("expected extra(name = \"...\", expr = \"...\")")),
+                }
+            } else {
+                Err(meta.error("unrecognized debug attribute"))
+            }
+        })?;
+    }
+
+    Ok(container)
+}
+
+// Container attributes under the name `name`, as in #[eq(...)].
+pub fn cmp_container(attrs: &[Attribute], name: &str) -> syn::Result<CmpContainerAttrs> {
+    let mut container = CmpContainerAttrs::default();
+
+    for attr in attrs {
+        if !attr.path().is_ident(name) {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("bound") {
+                container.bound = Some(bound(&meta)?);
+                Ok(())
+            } else {
+                Err(meta.error(format!("unrecognized {} attribute", name)))
+            }
+        })?;
+    }
+
+    Ok(container)
+}
+
+// Field attributes under the name `name`, as in #[eq(skip)].
+pub fn cmp_field(attrs: &[Attribute], name: &str) -> syn::Result<CmpFieldAttrs> {
+    let mut field = CmpFieldAttrs::default();
+
+    for attr in attrs {
+        if !attr.path().is_ident(name) {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if skip_or_with(&meta, &mut field.skip, &mut field.with)? {
+                Ok(())
+            } else {
+                Err(meta.error(format!("unrecognized {} attribute", name)))
+            }
+        })?;
+    }
+
+    if let (true, Some(with)) = (field.skip, &field.with) {
+        return Err(syn::Error::new_spanned(
+            with,
+            "`with` cannot be combined with `skip`",
+        ));
+    }
+
+    Ok(field)
+}
+
+// `skip` and `with = "..."`, which the field attributes of every derive
+// accept. Returns whether `meta` was either of them.
+fn skip_or_with(
+    meta: &ParseNestedMeta,
+    skip: &mut bool,
+    with: &mut Option<Path>,
+) -> syn::Result<bool> {
+    if meta.path.is_ident("skip") {
+        *skip = true;
+        Ok(true)
+    } else if meta.path.is_ident("with") {
+        *with = Some(path(meta)?);
+        Ok(true)
+    } else {
+        Ok(false)
+    }
+}
+
+// The value of `key = "..."` holding a path, as for `with`.
+fn path(meta: &ParseNestedMeta) -> syn::Result<Path> {
+    let lit: LitStr = meta.value()?.parse()?;
+    lit.parse()
+}
+
+// The value of `bound = "..."`.
+fn bound(meta: &ParseNestedMeta) -> syn::Result<Punctuated<WherePredicate, Token![,]>> {
+    let lit: LitStr = meta.value()?.parse()?;
+    lit.parse_with(Punctuated::parse_terminated)
+}
+
+// Whether the type is #[repr(packed)] or #[repr(packed(N))], possibly among
+// other representation hints.
+pub fn packed(attrs: &[Attribute]) -> syn::Result<bool> {
+    let mut packed = false;
+
+    for attr in attrs {
+        if !attr.path().is_ident("repr") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("packed") {
+                packed = true;
+            }
+            // Skip arguments, as in packed(2) or align(8).
+            if meta.input.peek(token::Paren) {
+                let content;
+                parenthesized!(content in meta.input);
+                content.parse::<TokenStream>()?;
+            }
+            Ok(())
+        })?;
+    }
+
+    Ok(packed)
+}
+
+pub fn field(attrs: &[Attribute]) -> syn::Result<FieldAttrs> {
+    let mut field = FieldAttrs::default();
+    // Where `truncate` or `bytes` was given, for errors about combining them.
+    let mut compact: Option<Ident> = None;
+    let mut flatten: Option<Ident> = None;
+    let mut sorted: Option<Ident> = None;
+
+    for attr in attrs {
+        if !attr.path().is_ident("debug") {
+            continue;
+        }
+        match &attr.meta {
+            Meta::NameValue(meta) => match &meta.value {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(lit), ..
+                }) => {
+                    crate::format::validate(lit)?;
+                    field.format = Some(lit.clone());
+                }
+                value => {
+                    return Err(syn::Error::new_spanned(
+                        value,
+                        "expected a format string: #[debug = \"...\"]",
+                    ))
+                }
+            },
+            Meta::List(_) => attr.parse_nested_meta(|meta| {
+                if skip_or_with(&meta, &mut field.skip, &mut field.with)? {
+                    Ok(())
+                } else if meta.path.is_ident("skip_if") {
+                    field.skip_if = Some(path(&meta)?);
+                    Ok(())
+                } else if meta.path.is_ident("rename") {
+                    field.rename = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("bound") {
+                    field.bound = Some(bound(&meta)?);
+                    Ok(())
+                } else if meta.path.is_ident("truncate") {
+                    let lit: LitInt = meta.value()?.parse()?;
+                    field.truncate = Some(lit.base10_parse()?);
+                    compact = meta.path.get_ident().cloned();
+                    Ok(())
+                } else if meta.path.is_ident("bytes") {
+                    field.bytes = true;
+                    compact = meta.path.get_ident().cloned();
+                    Ok(())
+                } else if meta.path.is_ident("flatten") {
+                    field.flatten = true;
+                    flatten = meta.path.get_ident().cloned();
+                    Ok(())
+                } else if meta.path.is_ident("sorted") {
+                    field.sorted = true;
+                    sorted = meta.path.get_ident().cloned();
+                    Ok(())
+                } else if meta.path.is_ident("show") {
+                    field.show = meta.path.get_ident().cloned();
+                    Ok(())
+                } else if meta.path.is_ident("prefix") {
+                    field.prefix = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("redact") {
+                    field.redact =
+                        Some(if meta.input.peek(Token![=]) {
+                            let lit: LitStr = meta.value()?.parse()?;
+                            match lit.value().as_str() {
+                                "len" => Redact::Len,
+                                "hash" => Redact::Hash,
+                                _ => return Err(syn::Error::new_spanned(
+                                    lit,
+                                    "expected `redact`, `redact = \"len\"` or `redact = \"hash\"`",
+                                )),
+                            }
+                        } else {
+                            Redact::Full
+                        });
+                    Ok(())
+                } else {
+                    Err(meta.error("unrecognized debug attribute"))
+                }
+            })?,
+            Meta::Path(_) => {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "expected #[debug = \"...\"] or #[debug(...)]",
+                ))
+            }
+        }
+    }
+
+    if let (Some(format), Some(_)) = (&field.format, &field.with) {
+        return Err(syn::Error::new_spanned(
+            format,
+            "#[debug = \"...\"] cannot be combined with `with`",
+        ));
+    }
+    if let (Some(with), Some(_)) = (&field.with, &field.redact) {
+        return Err(syn::Error::new_spanned(
+            with,
+            "`with` cannot be combined with `redact`",
+        ));
+    }
+
+    if let Some(key) = &compact {
+        let other = if field.format.is_some() {
+            Some("#[debug = \"...\"]")
+        } else if field.with.is_some() {
+            Some("`with`")
+        } else if field.redact.is_some() {
+            Some("`redact`")
+        } else {
+            None
+        };
+        if let Some(other) = other {
+            return Err(syn::Error::new_spanned(
+                key,
+                format!("`{}` cannot be combined with {}", key, other),
+            ));
+        }
+    }
+
+    if let (Some(show), true) = (&field.show, field.skip) {
+        return Err(syn::Error::new_spanned(
+            show,
+            "`show` cannot be combined with `skip`",
+        ));
+    }
+    if let (Some(prefix), false) = (&field.prefix, field.flatten) {
+        return Err(syn::Error::new_spanned(
+            prefix,
+            "`prefix` is only supported together with `flatten`",
+        ));
+    }
+    if let Some(key) = flatten {
+        let other = if field.format.is_some() {
+            Some("#[debug = \"...\"]")
+        } else if field.with.is_some() {
+            Some("`with`")
+        } else if field.redact.is_some() {
+            Some("`redact`")
+        } else if field.rename.is_some() {
+            Some("`rename`")
+        } else {
+            compact.as_ref().map(|_| "`truncate` or `bytes`")
+        };
+        if let Some(other) = other {
+            return Err(syn::Error::new_spanned(
+                &key,
+                format!("`flatten` cannot be combined with {}", other),
+            ));
+        }
+    }
+    if let Some(key) = sorted {
+        let other = if field.format.is_some() {
+            Some("#[debug = \"...\"]")
+        } else if field.with.is_some() {
+            Some("`with`")
+        } else if field.redact.is_some() {
+            Some("`redact`")
+        } else if field.flatten {
+            Some("`flatten`")
+        } else {
+            compact.as_ref().map(|_| "`truncate` or `bytes`")
+        };
+        if let Some(other) = other {
+            return Err(syn::Error::new_spanned(
+                &key,
+                format!("`sorted` cannot be combined with {}", other),
+            ));
+        }
+    }
+
+    Ok(field)
+}
@@ -0,0 +1,107 @@
+use quote::ToTokens;
+use std::collections::BTreeSet;
+use syn::visit::{self, Visit};
+use syn::{
+    parse_quote, Generics, Ident, Path, Type, TypeBareFn, TypeParen, TypePath, TypeTraitObject,
+    WherePredicate,
+};
+
+// Infers bounds on `bound`, usually `Debug`, from the types of the fields
+// being printed.
+//
+// A type parameter `T` used directly in a field gets a `T: Debug` bound, unless
+// it appears only inside of `PhantomData`, which is Debug regardless of `T`.
+// Associated types of type parameters, like `T::Value` or `<T as
+// Trait>::Value`, are bounded themselves rather than placing any bound on `T`,
+// wherever they are nested. Function pointers are Debug whatever their
+// signature, so parameters mentioned in one contribute nothing. A trait object
+// mentioning a parameter is bounded as a whole, since only the trait's
+// supertraits can make it Debug.
+pub fn infer<'a>(
+    generics: &Generics,
+    types: impl IntoIterator<Item = &'a Type>,
+    bound: &Path,
+) -> Vec<WherePredicate> {
+    let params: BTreeSet<&Ident> = generics.type_params().map(|param| &param.ident).collect();
+    if params.is_empty() {
+        return Vec::new();
+    }
+
+    let mut visitor = BoundVisitor {
+        params: &params,
+        bounded: Vec::new(),
+    };
+    for ty in types {
+        visitor.visit_type(ty);
+    }
+
+    let mut seen = BTreeSet::new();
+    visitor
+        .bounded
+        .into_iter()
+        .filter(|ty| seen.insert(ty.to_token_stream().to_string()))
+        .map(|ty| parse_quote!(#ty: #bound))
+        .collect()
+}
+
+struct BoundVisitor<'a> {
+    params: &'a BTreeSet<&'a Ident>,
+    bounded: Vec<Type>,
+}
+
+impl<'a, 'ast> Visit<'ast> for BoundVisitor<'a> {
+    fn visit_type_path(&mut self, ty: &'ast TypePath) {
+        if ty.qself.is_some() {
+            let ty = Type::Path(ty.clone());
+            if mentions_param(self.params, &ty) {
+                self.bounded.push(ty);
+            }
+            return;
+        }
+        let segments = &ty.path.segments;
+        if segments.last().unwrap().ident == "PhantomData" {
+            return;
+        }
+        if ty.path.leading_colon.is_none() && self.params.contains(&segments[0].ident) {
+            self.bounded.push(Type::Path(ty.clone()));
+            return;
+        }
+        visit::visit_type_path(self, ty);
+    }
+
+    fn visit_type_bare_fn(&mut self, _ty: &'ast TypeBareFn) {}
+
+    fn visit_type_trait_object(&mut self, ty: &'ast TypeTraitObject) {
+        // Parenthesized so that `dyn Trait<T> + 'a: Debug` parses.
+        let ty = Type::Paren(TypeParen {
+            paren_token: Default::default(),
+            elem: Box::new(Type::TraitObject(ty.clone())),
+        });
+        if mentions_param(self.params, &ty) {
+            self.bounded.push(ty);
+        }
+    }
+}
+
+fn mentions_param(params: &BTreeSet<&Ident>, ty: &Type) -> bool {
+    let mut visitor = ParamVisitor {
+        params,
+        found: false,
+    };
+    visitor.visit_type(ty);
+    visitor.found
+}
+
+struct ParamVisitor<'a> {
+    params: &'a BTreeSet<&'a Ident>,
+    found: bool,
+}
+
+impl<'a, 'ast> Visit<'ast> for ParamVisitor<'a> {
+    fn visit_path(&mut self, path: &'ast Path) {
+        if path.leading_colon.is_none() && self.params.contains(&path.segments[0].ident) {
+            self.found = true;
+        }
+        visit::visit_path(self, path);
+    }
+}
@@ -0,0 +1,265 @@
+use crate::attr::{self, CmpFieldAttrs};
+use crate::{DebugField, Style, Variant};
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::{parse_quote, Data, DeriveInput, Field, Ident, Path, WherePredicate};
+
+// #[derive(CustomPartialEq)] and #[derive(CustomHash)] write PartialEq and
+// Hash impls over the fields of a struct or enum, like the standard derives,
+// except that a field can be left out with #[eq(skip)] or #[hash(skip)], or
+// handled by a function of its own with #[eq(with = "...")], called as
+// `with(&a, &b) -> bool`, or #[hash(with = "...")], called as
+// `with(&value, &mut state)`.
+//
+// Bounds are inferred from the compared fields as CustomDebug infers them, and
+// can be replaced with #[eq(bound = "...")] or #[hash(bound = "...")].
+pub fn partial_eq(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let cmp = Cmp::parse(input, "eq", "CustomPartialEq", |field, _| {
+        attr::cmp_field(&field.attrs, "eq")
+    })?;
+    let name = &input.ident;
+    let generics = cmp.generics(input, parse_quote!(::std::cmp::PartialEq));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let arms = cmp.variants.iter().map(|variant| {
+        let pattern = variant.pattern();
+        let other_pattern = variant.pattern_with(other);
+        let copies = variant.copies(quote!(self));
+        let other_copies = variant.copies_with(quote!(other), other);
+        let fields = compared(variant).map(|field| {
+            let left = &field.binding;
+            let right = other(field);
+            match &field.attrs.with {
+                Some(with) => quote!(#with(#left, #right)),
+                None => quote!(#left == #right),
+            }
+        });
+        let fields: Vec<_> = fields.collect();
+        let eq = if fields.is_empty() {
+            quote!(true)
+        } else {
+            quote!(#(#fields)&&*)
+        };
+        quote! {
+            (#pattern, #other_pattern) => {
+                #copies
+                #other_copies
+                #eq
+            }
+        }
+    });
+    // Values of different variants are never equal.
+    let rest = if cmp.variants.len() > 1 {
+        Some(quote!(_ => false,))
+    } else {
+        None
+    };
+    let body = if cmp.variants.is_empty() {
+        quote!(match *self {})
+    } else {
+        quote! {
+            match (self, other) {
+                #(#arms)*
+                #rest
+            }
+        }
+    };
+    let packed = cmp.assert_packed("compared by CustomPartialEq", "eq");
+
+    Ok(quote! {
+        impl #impl_generics ::std::cmp::PartialEq for #name #ty_generics #where_clause {
+            fn eq(&self, other: &Self) -> bool {
+                #packed
+                #body
+            }
+        }
+    })
+}
+
+pub fn hash(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let cmp = Cmp::parse(input, "hash", "CustomHash", |field, _| {
+        let hash = attr::cmp_field(&field.attrs, "hash")?;
+        // Errors in #[eq(...)] are left to CustomPartialEq.
+        if let Ok(eq) = attr::cmp_field(&field.attrs, "eq") {
+            consistent(field, &eq, &hash)?;
+        }
+        Ok(hash)
+    })?;
+    let name = &input.ident;
+    let generics = cmp.generics(input, parse_quote!(::std::hash::Hash));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let arms = cmp.variants.iter().map(|variant| {
+        let pattern = variant.pattern();
+        let copies = variant.copies(quote!(self));
+        // As in the standard derive, the variant is hashed first so that
+        // variants holding equal fields hash differently.
+        let discriminant = variant
+            .ident
+            .map(|_| quote!(::std::hash::Hash::hash(&::std::mem::discriminant(self), state);));
+        let fields = compared(variant).map(|field| {
+            let binding = &field.binding;
+            match &field.attrs.with {
+                Some(with) => quote!(#with(#binding, state);),
+                None => quote!(::std::hash::Hash::hash(#binding, state);),
+            }
+        });
+        quote! {
+            #pattern => {
+                #copies
+                #discriminant
+                #(#fields)*
+            }
+        }
+    });
+    let body = if cmp.variants.is_empty() {
+        quote!(match *self {})
+    } else {
+        quote! {
+            match self {
+                #(#arms)*
+            }
+        }
+    };
+    let packed = cmp.assert_packed("hashed by CustomHash", "hash");
+
+    Ok(quote! {
+        impl #impl_generics ::std::hash::Hash for #name #ty_generics #where_clause {
+            fn hash<__H: ::std::hash::Hasher>(&self, state: &mut __H) {
+                #packed
+                #body
+            }
+        }
+    })
+}
+
+// Values that are equal must hash equally, so a field that #[eq(...)] leaves
+// out or compares with a function of its own can't be hashed by its Hash impl.
+fn consistent(field: &Field, eq: &CmpFieldAttrs, hash: &CmpFieldAttrs) -> syn::Result<()> {
+    if hash.skip || hash.with.is_some() {
+        return Ok(());
+    }
+    let message = if eq.skip {
+        "field is skipped by #[eq(skip)], so it must be skipped by #[hash(skip)] too, \
+         or equal values could hash differently"
+    } else if eq.with.is_some() {
+        "field is compared by #[eq(with = \"...\")], so it needs #[hash(with = \"...\")] \
+         or #[hash(skip)] too, or equal values could hash differently"
+    } else {
+        return Ok(());
+    };
+    let attr = field.attrs.iter().find(|attr| attr.path().is_ident("eq"));
+    Err(match attr {
+        Some(attr) => syn::Error::new_spanned(attr, message),
+        None => syn::Error::new_spanned(&field.ty, message),
+    })
+}
+
+struct Cmp<'a> {
+    variants: Vec<Variant<'a, CmpFieldAttrs>>,
+    bound: Option<Vec<WherePredicate>>,
+}
+
+impl<'a> Cmp<'a> {
+    // `name` is the name of the container attributes, and `derive` the name
+    // of the derive for errors. Field attributes are parsed with `attrs`.
+    fn parse(
+        input: &'a DeriveInput,
+        name: &str,
+        derive: &str,
+        mut attrs: impl FnMut(&'a Field, &Style) -> syn::Result<CmpFieldAttrs>,
+    ) -> syn::Result<Self> {
+        let container = attr::cmp_container(&input.attrs, name)?;
+        let mut variants = match &input.data {
+            Data::Struct(data) => vec![Variant::parse_with(None, &data.fields, &mut attrs)?],
+            Data::Enum(data) => {
+                let mut errors = Vec::new();
+                let mut variants = Vec::new();
+                for variant in &data.variants {
+                    match Variant::parse_with(Some(&variant.ident), &variant.fields, &mut attrs) {
+                        Ok(variant) => variants.push(variant),
+                        Err(err) => errors.push(err),
+                    }
+                }
+                if let Some(err) = crate::combine(errors) {
+                    return Err(err);
+                }
+                variants
+            }
+            Data::Union(_) => {
+                return Err(syn::Error::new(
+                    Span::call_site(),
+                    format!("{} cannot be derived for unions", derive),
+                ))
+            }
+        };
+
+        if attr::packed(&input.attrs)? {
+            // Only structs can be packed.
+            for variant in &mut variants {
+                variant.packed = true;
+            }
+        }
+
+        Ok(Cmp {
+            variants,
+            bound: container.bound.map(|bound| bound.into_iter().collect()),
+        })
+    }
+
+    fn packed(&self) -> bool {
+        self.variants.iter().any(|variant| variant.packed)
+    }
+
+    // Generics of the impl, with bounds on `bound`, the trait being derived.
+    fn generics(&self, input: &DeriveInput, bound: Path) -> syn::Generics {
+        let all = || self.variants.iter().flat_map(compared);
+        let bounds = match &self.bound {
+            Some(bound) => bound.clone(),
+            None => {
+                let mut bounds = crate::bound::infer(
+                    &input.generics,
+                    all()
+                        .filter(|field| field.attrs.with.is_none())
+                        .map(|field| field.ty),
+                    &bound,
+                );
+                // Fields of a packed struct are copied out before use.
+                if self.packed() {
+                    bounds.extend(crate::bound::infer(
+                        &input.generics,
+                        all().map(|field| field.ty),
+                        &parse_quote!(::std::marker::Copy),
+                    ));
+                }
+                bounds
+            }
+        };
+        let mut generics = input.generics.clone();
+        generics.make_where_clause().predicates.extend(bounds);
+        generics
+    }
+
+    fn assert_packed(&self, usage: &str, name: &str) -> Option<TokenStream2> {
+        if !self.packed() {
+            return None;
+        }
+        let types = self
+            .variants
+            .iter()
+            .flat_map(compared)
+            .map(|field| field.ty);
+        Some(crate::assert_packed(types, usage, name))
+    }
+}
+
+fn compared<'v, 'a>(
+    variant: &'v Variant<'a, CmpFieldAttrs>,
+) -> impl Iterator<Item = &'v DebugField<'a, CmpFieldAttrs>> {
+    variant.fields.iter().filter(|field| !field.attrs.skip)
+}
+
+// Name the field of `other` is bound to.
+fn other(field: &DebugField<CmpFieldAttrs>) -> Ident {
+    format_ident!("{}_other", field.binding)
+}
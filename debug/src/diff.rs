@@ -0,0 +1,193 @@
+use crate::{DebugField, Variant};
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::parse::Parser;
+use syn::punctuated::Punctuated;
+use syn::{Expr, Token};
+
+// assert_debug_eq!(left, right) compares two values by their Debug output. For
+// a struct deriving CustomDebug, the differences are reported field by field,
+// recursing into fields whose types derive it too, so that a failure on two
+// large structs names the fields that differ instead of printing both whole:
+//
+//     assertion `left == right` failed (Debug output differs)
+//       conn.port:
+//          left: 8080
+//         right: 8081
+//
+// Any further arguments form a custom message, as with assert_eq!.
+pub fn assert_debug_eq(input: TokenStream2) -> syn::Result<TokenStream2> {
+    let args = Punctuated::<Expr, Token![,]>::parse_terminated.parse2(input)?;
+    let mut args = args.into_iter();
+    let (left, right) = match (args.next(), args.next()) {
+        (Some(left), Some(right)) => (left, right),
+        _ => {
+            return Err(syn::Error::new(
+                proc_macro2::Span::call_site(),
+                "expected assert_debug_eq!(left, right)",
+            ))
+        }
+    };
+    let message: Vec<Expr> = args.collect();
+    let message = if message.is_empty() {
+        quote!(" (Debug output differs)")
+    } else {
+        quote!(::std::format!(": {}", ::std::format_args!(#(#message),*)))
+    };
+
+    let fallback = fallback();
+    Ok(quote! {
+        match (&(#left), &(#right)) {
+            (__left, __right) => {
+                #fallback
+                let mut __diffs = ::std::vec::Vec::new();
+                __left.__debug_diff(__right, "", &mut __diffs);
+                if !__diffs.is_empty() {
+                    let mut __report = ::std::string::String::new();
+                    for (path, left, right) in &__diffs {
+                        let indent = if path.is_empty() {
+                            ""
+                        } else {
+                            __report += &::std::format!("\n  {}:", path);
+                            "  "
+                        };
+                        __report += &::std::format!(
+                            "\n{0}   left: {1}\n{0}  right: {2}",
+                            indent, left, right,
+                        );
+                    }
+                    ::std::panic!("assertion `left == right` failed{}{}", #message, __report);
+                }
+            }
+        }
+    })
+}
+
+// Inherent methods of a struct with named fields that collect the fields
+// differing between `self` and `other` as (path, left, right).
+//
+// Fields call `__debug_diff` on themselves. Method resolution prefers the
+// inherent method of a type deriving CustomDebug over the blanket fallback,
+// which compares whole Debug output, so the comparison recurses exactly into
+// the fields that support it.
+pub fn methods(variant: &Variant, support: &TokenStream2) -> TokenStream2 {
+    let other = |field: &DebugField| format_ident!("{}_other", field.binding);
+    let pattern = variant.pattern();
+    let other_pattern = variant.pattern_with(other);
+    let copies = variant.copies(quote!(self));
+    let other_copies = variant.copies_with(quote!(other), other);
+    let fallback = fallback();
+
+    let entries = variant
+        .fields
+        .iter()
+        .filter(|field| !field.attrs.skip)
+        .map(|field| {
+            let binding = &field.binding;
+            let other = other(field);
+            if field.attrs.flatten {
+                let prefix = match &field.attrs.prefix {
+                    Some(prefix) => prefix.value(),
+                    None => String::new(),
+                };
+                return quote! {
+                    #binding.__debug_diff_fields(
+                        #other,
+                        &::std::format!("{}{}", __prefix, #prefix),
+                        __diffs,
+                    );
+                };
+            }
+            let name = field.name();
+            let path = quote!(&::std::format!("{}{}", __prefix, #name));
+            if field.plain() {
+                quote!(#binding.__debug_diff(#other, #path, __diffs);)
+            } else {
+                // Compared as printed, so that redacted values stay hidden.
+                let left = field.value();
+                let right = field.render(&other);
+                quote!(__DebugDiff::__debug_diff(#left, #right, #path, __diffs);)
+            }
+        });
+
+    quote! {
+        #[doc(hidden)]
+        #[allow(dead_code)]
+        pub fn __debug_diff(
+            &self,
+            other: &Self,
+            path: &str,
+            diffs: &mut ::std::vec::Vec<(
+                ::std::string::String,
+                ::std::string::String,
+                ::std::string::String,
+            )>,
+        ) {
+            if path.is_empty() {
+                self.__debug_diff_fields(other, "", diffs);
+            } else {
+                self.__debug_diff_fields(other, &::std::format!("{}.", path), diffs);
+            }
+        }
+
+        #[doc(hidden)]
+        #[allow(dead_code)]
+        pub fn __debug_diff_fields(
+            &self,
+            other: &Self,
+            __prefix: &str,
+            __diffs: &mut ::std::vec::Vec<(
+                ::std::string::String,
+                ::std::string::String,
+                ::std::string::String,
+            )>,
+        ) {
+            #support
+            #fallback
+            let #pattern = self;
+            let #other_pattern = other;
+            #copies
+            #other_copies
+            #(#entries)*
+        }
+    }
+}
+
+// Comparison of any two Debug values by their output, for values whose type
+// doesn't derive CustomDebug.
+fn fallback() -> TokenStream2 {
+    quote! {
+        #[allow(dead_code)]
+        trait __DebugDiff {
+            fn __debug_diff(
+                &self,
+                other: &Self,
+                path: &str,
+                diffs: &mut ::std::vec::Vec<(
+                    ::std::string::String,
+                    ::std::string::String,
+                    ::std::string::String,
+                )>,
+            );
+        }
+
+        impl<T: ?::std::marker::Sized + ::std::fmt::Debug> __DebugDiff for T {
+            fn __debug_diff(
+                &self,
+                other: &Self,
+                path: &str,
+                diffs: &mut ::std::vec::Vec<(
+                    ::std::string::String,
+                    ::std::string::String,
+                    ::std::string::String,
+                )>,
+            ) {
+                let left = ::std::format!("{:?}", self);
+                let right = ::std::format!("{:?}", other);
+                if left != right {
+                    diffs.push((::std::borrow::ToOwned::to_owned(path), left, right));
+                }
+            }
+        }
+    }
+}
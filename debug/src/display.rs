@@ -0,0 +1,301 @@
+use crate::format::{self, Argument, ArgumentKind, Syntax};
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote, ToTokens};
+use std::collections::BTreeSet;
+use syn::ext::IdentExt;
+use syn::{
+    parse_quote, Attribute, Data, DeriveInput, Fields, Ident, LitStr, Member, Path, Type,
+    WherePredicate,
+};
+
+// #[derive(CustomDisplay)] writes a Display impl from a template on the struct,
+// or on every variant of an enum, whose placeholders name the fields to print:
+//
+//     #[derive(CustomDisplay)]
+//     #[display("{name} (mask {bitmask:#b})")]
+//     pub struct Field {
+//         name: &'static str,
+//         bitmask: u8,
+//     }
+//
+// Tuple fields are referred to by index as `{0}`, or in order as `{}`. Only the
+// fields a template refers to are bound, each by the formatting trait it is
+// used with.
+pub fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+
+    let variants = match &input.data {
+        Data::Struct(data) => {
+            let template = match template(&input.attrs)? {
+                Some(template) => template,
+                None => {
+                    return Err(syn::Error::new(
+                        Span::call_site(),
+                        "missing #[display(\"...\")] template on the struct",
+                    ))
+                }
+            };
+            vec![Variant::parse(name, None, &data.fields, template)?]
+        }
+        Data::Enum(data) => {
+            let mut errors = Vec::new();
+            let mut variants = Vec::new();
+            for variant in &data.variants {
+                let template = match template(&variant.attrs) {
+                    Ok(Some(template)) => template,
+                    Ok(None) => {
+                        errors.push(syn::Error::new_spanned(
+                            &variant.ident,
+                            "missing #[display(\"...\")] template on the variant",
+                        ));
+                        continue;
+                    }
+                    Err(err) => {
+                        errors.push(err);
+                        continue;
+                    }
+                };
+                match Variant::parse(
+                    &variant.ident,
+                    Some(&variant.ident),
+                    &variant.fields,
+                    template,
+                ) {
+                    Ok(variant) => variants.push(variant),
+                    Err(err) => errors.push(err),
+                }
+            }
+            if let Some(err) = crate::combine(errors) {
+                return Err(err);
+            }
+            variants
+        }
+        Data::Union(_) => {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                "CustomDisplay cannot be derived for unions",
+            ))
+        }
+    };
+
+    let mut generics = input.generics.clone();
+    let mut seen = BTreeSet::new();
+    let bounds: Vec<WherePredicate> = variants
+        .iter()
+        .flat_map(|variant| &variant.uses)
+        .flat_map(|(ty, bound)| crate::bound::infer(&input.generics, [*ty], bound))
+        .filter(|predicate| seen.insert(predicate.to_token_stream().to_string()))
+        .collect();
+    generics.make_where_clause().predicates.extend(bounds);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let body = if variants.is_empty() {
+        quote!(match *self {})
+    } else {
+        let arms = variants.iter().map(Variant::arm);
+        quote! {
+            match self {
+                #(#arms)*
+            }
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::std::fmt::Display for #name #ty_generics #where_clause {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                #body
+            }
+        }
+    })
+}
+
+fn template(attrs: &[Attribute]) -> syn::Result<Option<LitStr>> {
+    let mut template = None;
+    for attr in attrs {
+        if !attr.path().is_ident("display") {
+            continue;
+        }
+        if template.is_some() {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "duplicate #[display(\"...\")] template",
+            ));
+        }
+        template = Some(attr.parse_args::<LitStr>()?);
+    }
+    Ok(template)
+}
+
+struct Variant<'a> {
+    ident: Option<&'a Ident>,
+    // Pattern entries for every field, binding those the template refers to.
+    bindings: Vec<TokenStream2>,
+    // Widths and precisions taken from fields, copied out as usize.
+    counts: Vec<(Ident, Ident)>,
+    template: LitStr,
+    // Type of every field printed by the template, with its formatting trait.
+    uses: Vec<(&'a Type, Path)>,
+}
+
+impl<'a> Variant<'a> {
+    // `owner` names the struct or variant in error messages.
+    fn parse(
+        owner: &Ident,
+        ident: Option<&'a Ident>,
+        fields: &'a Fields,
+        template: LitStr,
+    ) -> syn::Result<Self> {
+        let arguments = format::parse(&template)?;
+        let fields: Vec<_> = fields.iter().collect();
+
+        let mut errors = Vec::new();
+        let mut referenced = vec![false; fields.len()];
+        let mut counts = Vec::new();
+        let mut uses = Vec::new();
+        let mut replacements = Vec::new();
+        for argument in &arguments {
+            let i = match resolve(&template, owner, &fields, argument) {
+                Ok(i) => i,
+                Err(err) => {
+                    errors.push(err);
+                    continue;
+                }
+            };
+            let binding = format_ident!("__self_{}", i);
+            let name = match &argument.format_trait {
+                Some(format_trait) => {
+                    uses.extend(trait_path(format_trait).map(|bound| (&fields[i].ty, bound)));
+                    binding.clone()
+                }
+                None => {
+                    let count = format_ident!("__count_{}", i);
+                    if !counts.iter().any(|(name, _)| *name == count) {
+                        counts.push((count.clone(), binding.clone()));
+                    }
+                    count
+                }
+            };
+            referenced[i] = true;
+            let name = match argument.syntax {
+                Syntax::Star => format!("{}$", name),
+                Syntax::Implicit | Syntax::Explicit => name.to_string(),
+            };
+            replacements.push((argument.range.clone(), name));
+        }
+        if let Some(err) = crate::combine(errors) {
+            return Err(err);
+        }
+
+        // Placeholders are rewritten to name the bindings, which format_args!
+        // then captures from the match arm.
+        let mut value = template.value();
+        replacements.sort_by_key(|(range, _)| range.start);
+        for (range, name) in replacements.into_iter().rev() {
+            value.replace_range(range, &name);
+        }
+
+        let bindings = fields
+            .iter()
+            .enumerate()
+            .map(|(i, field)| {
+                let member = match &field.ident {
+                    Some(ident) => Member::Named(ident.clone()),
+                    None => Member::Unnamed(i.into()),
+                };
+                if referenced[i] {
+                    let binding = format_ident!("__self_{}", i);
+                    quote!(#member: #binding)
+                } else {
+                    quote!(#member: _)
+                }
+            })
+            .collect();
+
+        Ok(Variant {
+            ident,
+            bindings,
+            counts,
+            template: LitStr::new(&value, template.span()),
+            uses,
+        })
+    }
+
+    fn arm(&self) -> TokenStream2 {
+        let path = match self.ident {
+            Some(ident) => quote!(Self::#ident),
+            None => quote!(Self),
+        };
+        let bindings = &self.bindings;
+        let counts = self
+            .counts
+            .iter()
+            .map(|(count, binding)| quote!(let #count: usize = *#binding;));
+        let template = &self.template;
+        quote! {
+            #path { #(#bindings),* } => {
+                #(#counts)*
+                ::std::write!(f, #template)
+            }
+        }
+    }
+}
+
+// Index of the field that a placeholder argument refers to.
+fn resolve(
+    template: &LitStr,
+    owner: &Ident,
+    fields: &[&syn::Field],
+    argument: &Argument,
+) -> syn::Result<usize> {
+    let range = match argument.syntax {
+        Syntax::Implicit => argument.placeholder.clone(),
+        Syntax::Explicit | Syntax::Star => argument.range.clone(),
+    };
+    let named = fields.first().is_some_and(|field| field.ident.is_some());
+    let message = match &argument.kind {
+        ArgumentKind::Name(name) => {
+            let position = fields.iter().position(|field| match &field.ident {
+                Some(ident) => ident.unraw() == name,
+                None => false,
+            });
+            match position {
+                Some(i) => return Ok(i),
+                None => format!("no field `{}` on `{}`", name, owner.unraw()),
+            }
+        }
+        ArgumentKind::Index(index) if !named => {
+            if *index < fields.len() {
+                return Ok(*index);
+            }
+            format!(
+                "no field `{}` on `{}`, which has {} field{}",
+                index,
+                owner.unraw(),
+                fields.len(),
+                if fields.len() == 1 { "" } else { "s" },
+            )
+        }
+        ArgumentKind::Index(_) => format!(
+            "`{}` has named fields; refer to them by name, like `{{{}}}`",
+            owner.unraw(),
+            fields[0].ident.as_ref().unwrap().unraw(),
+        ),
+    };
+    Err(format::error(template, range, message))
+}
+
+// Trait that a field formatted with `format_trait` must implement. Fields are
+// bound by reference, so `{:p}` prints the field's address whatever its type.
+fn trait_path(format_trait: &str) -> Option<Path> {
+    Some(match format_trait {
+        "?" | "x?" | "X?" => parse_quote!(::std::fmt::Debug),
+        "x" => parse_quote!(::std::fmt::LowerHex),
+        "X" => parse_quote!(::std::fmt::UpperHex),
+        "o" => parse_quote!(::std::fmt::Octal),
+        "b" => parse_quote!(::std::fmt::Binary),
+        "e" => parse_quote!(::std::fmt::LowerExp),
+        "E" => parse_quote!(::std::fmt::UpperExp),
+        "p" => return None,
+        _ => parse_quote!(::std::fmt::Display),
+    })
+}
@@ -0,0 +1,161 @@
+use crate::Variant;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::visit_mut::{self, VisitMut};
+use syn::{parse_quote, DeriveInput, GenericParam, Generics, Member, Type, TypePath};
+
+// `debug_fields(&self)` yields every printed field of the value as a
+// (name, value) pair, for consumers like structured loggers that want key
+// value pairs rather than one formatted string. Names follow #[debug(rename)]
+// and tuple fields are named by index. A flattened field is yielded whole
+// under its own name, and extra entries, which are computed on the fly, are
+// not yielded at all.
+//
+// Fields printed through their Debug impl are yielded as they are. Any other
+// field is yielded as a reference to a #[repr(transparent)] wrapper around
+// it, declared inside `debug_fields`, whose Debug impl applies the field's
+// format string, redaction and so on. The wrappers can't use the generics of
+// the enclosing impl, so they declare the type's own, and their Debug impls
+// take `generics`, those of the Debug impl including inferred bounds. Inside
+// a wrapper, `Self` is the wrapper, so any `Self` in the field's type and the
+// bounds is replaced by the type itself.
+pub fn debug_fields(
+    input: &DeriveInput,
+    variants: &[Variant],
+    generics: &Generics,
+    support: &TokenStream2,
+) -> TokenStream2 {
+    let name = &input.ident;
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+    let mut replace = ReplaceSelf {
+        ty: parse_quote!(#name #ty_generics),
+    };
+    let mut generics = generics.clone();
+    replace.visit_generics_mut(&mut generics);
+    let mut decl = input.generics.clone();
+    replace.visit_generics_mut(&mut decl);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let (decl_generics, _, decl_where_clause) = decl.split_for_impl();
+    let turbofish = ty_generics.as_turbofish();
+
+    // Every type and lifetime parameter must be used by a wrapper struct.
+    let phantom = input
+        .generics
+        .params
+        .iter()
+        .filter_map(|param| match param {
+            GenericParam::Type(param) => {
+                let ident = &param.ident;
+                Some(quote!(*const #ident))
+            }
+            GenericParam::Lifetime(param) => {
+                let lifetime = &param.lifetime;
+                Some(quote!(&#lifetime ()))
+            }
+            GenericParam::Const(_) => None,
+        });
+    let phantom = quote!(::std::marker::PhantomData<fn() -> (#(#phantom,)*)>);
+
+    let mut wrappers = Vec::new();
+    let mut arms = Vec::new();
+    for (v, variant) in variants.iter().enumerate() {
+        let mut entries = Vec::new();
+        for field in variant.fields.iter().filter(|field| !field.attrs.skip) {
+            let binding = &field.binding;
+            let key = match &field.member {
+                Member::Named(_) => field.name(),
+                Member::Unnamed(index) => index.index.to_string(),
+            };
+
+            let value = if field.plain() || field.attrs.flatten {
+                quote!(#binding)
+            } else {
+                let wrapper = format_ident!("__DebugField{}_{}", v, binding);
+                let mut ty = field.ty.clone();
+                replace.visit_type_mut(&mut ty);
+                let render = field.value();
+                wrappers.push(quote! {
+                    #[repr(transparent)]
+                    #[allow(non_camel_case_types)]
+                    struct #wrapper #decl_generics (#phantom, #ty) #decl_where_clause;
+
+                    impl #impl_generics ::std::fmt::Debug for #wrapper #ty_generics #where_clause {
+                        fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                            #support
+                            let #binding = &self.1;
+                            ::std::fmt::Debug::fmt(#render, f)
+                        }
+                    }
+                });
+                // SAFETY: the wrapper is #[repr(transparent)] over the field's
+                // type, next to nothing but PhantomData.
+                quote!(unsafe { &*(#binding as *const #ty as *const #wrapper #turbofish) })
+            };
+
+            let push = quote!(__fields.push((#key, #value)););
+            entries.push(match &field.attrs.skip_if {
+                Some(skip_if) => quote! {
+                    if !#skip_if(#binding) {
+                        #push
+                    }
+                },
+                None => push,
+            });
+        }
+
+        let pattern = variant.pattern();
+        arms.push(quote! {
+            #pattern => {
+                #(#entries)*
+            }
+        });
+    }
+
+    quote! {
+        impl #impl_generics #name #ty_generics #where_clause {
+            /// Iterates over the fields printed by the Debug impl, as pairs of
+            /// name and value.
+            #[allow(dead_code)]
+            pub fn debug_fields(
+                &self,
+            ) -> impl ::std::iter::Iterator<Item = (&'static str, &dyn ::std::fmt::Debug)> {
+                #(#wrappers)*
+
+                #[allow(unused_mut)]
+                let mut __fields: ::std::vec::Vec<(&'static str, &dyn ::std::fmt::Debug)> =
+                    ::std::vec::Vec::new();
+                match self {
+                    #(#arms)*
+                }
+                __fields.into_iter()
+            }
+        }
+    }
+}
+
+// Replaces `Self` with `ty`, as a whole type or as the start of a path like
+// `Self::Item`.
+struct ReplaceSelf {
+    ty: Type,
+}
+
+impl VisitMut for ReplaceSelf {
+    fn visit_type_mut(&mut self, ty: &mut Type) {
+        if let Type::Path(TypePath { qself: None, path }) = ty {
+            if path.is_ident("Self") {
+                *ty = self.ty.clone();
+                return;
+            }
+            if path.leading_colon.is_none()
+                && path.segments.len() > 1
+                && path.segments[0].ident == "Self"
+                && path.segments[0].arguments.is_none()
+            {
+                let self_ty = &self.ty;
+                let rest = path.segments.iter().skip(1);
+                *ty = parse_quote!(<#self_ty>::#(#rest)::*);
+            }
+        }
+        visit_mut::visit_type_mut(self, ty);
+    }
+}
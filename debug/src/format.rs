@@ -0,0 +1,366 @@
+use proc_macro2::Span;
+use std::ops::Range;
+use syn::LitStr;
+
+// One reference to a format argument within a format string.
+pub struct Argument {
+    pub kind: ArgumentKind,
+    pub syntax: Syntax,
+    // Byte range of the argument as written. For an implicit `{}` this is the
+    // empty range right after the opening brace, where a name could go.
+    pub range: Range<usize>,
+    // Byte range of the whole `{...}` placeholder.
+    pub placeholder: Range<usize>,
+    // Formatting trait of the value, like "" for Display or "?" for Debug.
+    // None when the argument is a width or precision.
+    pub format_trait: Option<String>,
+}
+
+pub enum ArgumentKind {
+    Index(usize),
+    Name(String),
+}
+
+#[derive(PartialEq)]
+pub enum Syntax {
+    // `{}`, taking the next argument in order.
+    Implicit,
+    // `{0}`, `{name}`, `{:1$}` or `{:.name$}`.
+    Explicit,
+    // `{:.*}`, taking the precision from the next argument in order.
+    Star,
+}
+
+// Parses a format string with the syntax of format_args!, returning every
+// argument it refers to in the order they appear.
+pub fn parse(lit: &LitStr) -> syn::Result<Vec<Argument>> {
+    let value = lit.value();
+    let mut parser = Parser {
+        chars: value.char_indices().collect(),
+        len: value.len(),
+        pos: 0,
+        implicit: 0,
+        arguments: Vec::new(),
+    };
+    match parser.parse() {
+        Ok(()) => Ok(parser.arguments),
+        Err((range, message)) => Err(error(lit, range, message)),
+    }
+}
+
+// Checks a #[debug = "..."] format string before it reaches format_args!,
+// whose errors would otherwise point into the generated impl.
+//
+// The field value is the only argument available to the format string, so
+// every placeholder must refer to it: either implicitly as `{}` (only once,
+// since a second `{}` would be the next argument) or explicitly as `{0}`.
+// Named arguments and width or precision arguments other than `0$` are
+// rejected, as is a format string that never uses the field.
+pub fn validate(lit: &LitStr) -> syn::Result<()> {
+    let arguments = parse(lit)?;
+
+    for argument in &arguments {
+        match &argument.kind {
+            ArgumentKind::Index(0) => {}
+            ArgumentKind::Index(index) => {
+                let range = match argument.syntax {
+                    Syntax::Explicit => argument.range.clone(),
+                    Syntax::Implicit | Syntax::Star => argument.placeholder.clone(),
+                };
+                return Err(error(
+                    lit,
+                    range,
+                    format!(
+                        "format string refers to argument {} but the field is the only argument; use `{{}}` or `{{0}}` to refer to it",
+                        index,
+                    ),
+                ));
+            }
+            ArgumentKind::Name(name) => {
+                let hint = if argument.format_trait.is_some() {
+                    ", refer to it as `{}`"
+                } else {
+                    ""
+                };
+                return Err(error(
+                    lit,
+                    argument.range.clone(),
+                    format!(
+                        "named argument `{}` is not available; the field is the only argument{}",
+                        name, hint,
+                    ),
+                ));
+            }
+        }
+    }
+
+    if arguments.is_empty() {
+        return Err(error(
+            lit,
+            0..lit.value().len(),
+            "format string never uses the field; add a `{}` placeholder".to_owned(),
+        ));
+    }
+    Ok(())
+}
+
+// Error about the part of the format string at `range`.
+pub fn error(lit: &LitStr, range: Range<usize>, mut message: String) -> syn::Error {
+    let value = lit.value();
+    // Spans inside of a literal are not available on every compiler, so the
+    // message also says where the problem is.
+    if range != (0..value.len()) && !range.is_empty() {
+        message += &format!(" (`{}` at offset {})", &value[range.clone()], range.start);
+    }
+    syn::Error::new(span(lit, &value, range), message)
+}
+
+type Error = (Range<usize>, String);
+
+struct Parser {
+    chars: Vec<(usize, char)>,
+    len: usize,
+    // Index into `chars` of the next character to read.
+    pos: usize,
+    // Index of the argument that the next implicit `{}` or `.*` refers to.
+    implicit: usize,
+    arguments: Vec<Argument>,
+}
+
+const TRAITS: &[&str] = &["", "?", "x?", "X?", "x", "X", "o", "b", "e", "E", "p"];
+
+impl Parser {
+    fn parse(&mut self) -> Result<(), Error> {
+        while let Some(c) = self.bump() {
+            let start = self.pos - 1;
+            match c {
+                '{' if self.peek() == Some('{') => {
+                    self.bump();
+                }
+                '{' => self.placeholder(start)?,
+                '}' if self.peek() == Some('}') => {
+                    self.bump();
+                }
+                '}' => return Err((
+                    self.range(start, self.pos),
+                    "invalid format string: unmatched `}` found; use `}}` to print a literal `}`"
+                        .to_owned(),
+                )),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    // Parses one `{...}` placeholder whose opening brace is at `start`.
+    fn placeholder(&mut self, start: usize) -> Result<(), Error> {
+        let first = self.arguments.len();
+        let arg_start = self.pos;
+        let explicit = match self.integer() {
+            Some(index) => Some(ArgumentKind::Index(index)),
+            None => self.identifier().map(ArgumentKind::Name),
+        };
+        let arg_end = self.pos;
+
+        let mut format_trait = String::new();
+        if self.peek() == Some(':') {
+            self.bump();
+            format_trait = self.spec()?;
+        }
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.bump();
+        }
+        match self.bump() {
+            Some('}') => {}
+            Some(c) => {
+                return Err((
+                    self.range(start, self.pos),
+                    format!("invalid format string: expected `}}`, found `{}`", c),
+                ))
+            }
+            None => {
+                return Err((
+                    self.range(start, self.pos),
+                    "invalid format string: expected `}` but string was terminated; use `{{` to print a literal `{`"
+                        .to_owned(),
+                ))
+            }
+        }
+
+        // An implicit value comes after any `.*` precision in argument order.
+        let (kind, syntax) = match explicit {
+            Some(kind) => (kind, Syntax::Explicit),
+            None => (self.next_implicit(), Syntax::Implicit),
+        };
+        self.arguments.push(Argument {
+            kind,
+            syntax,
+            range: self.range(arg_start, arg_end),
+            placeholder: 0..0,
+            format_trait: Some(format_trait),
+        });
+        let placeholder = self.range(start, self.pos);
+        for argument in &mut self.arguments[first..] {
+            argument.placeholder = placeholder.clone();
+        }
+        Ok(())
+    }
+
+    // [[fill]align][sign]['#']['0'][width]['.' precision]type
+    //
+    // Returns the format trait.
+    fn spec(&mut self) -> Result<String, Error> {
+        if matches!(self.peek_nth(1), Some('<' | '^' | '>')) {
+            self.pos += 2;
+        } else if matches!(self.peek(), Some('<' | '^' | '>')) {
+            self.pos += 1;
+        }
+        if matches!(self.peek(), Some('+' | '-')) {
+            self.pos += 1;
+        }
+        if self.peek() == Some('#') {
+            self.pos += 1;
+        }
+        if self.peek() == Some('0') && self.peek_nth(1) != Some('$') {
+            self.pos += 1;
+        }
+
+        self.count();
+
+        if self.peek() == Some('.') {
+            let dot = self.pos;
+            self.pos += 1;
+            if self.peek() == Some('*') {
+                let kind = self.next_implicit();
+                self.arguments.push(Argument {
+                    kind,
+                    syntax: Syntax::Star,
+                    range: self.range(self.pos, self.pos + 1),
+                    placeholder: 0..0,
+                    format_trait: None,
+                });
+                self.pos += 1;
+            } else if !self.count() {
+                return Err((
+                    self.range(dot, self.pos),
+                    "invalid format string: expected a precision after `.`".to_owned(),
+                ));
+            }
+        }
+
+        let trait_start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '?')
+        {
+            self.pos += 1;
+        }
+        let name: String = self.chars[trait_start..self.pos]
+            .iter()
+            .map(|c| c.1)
+            .collect();
+        if !TRAITS.contains(&name.as_str()) {
+            return Err((
+                self.range(trait_start, self.pos),
+                format!("unknown format trait `{}`", name),
+            ));
+        }
+        Ok(name)
+    }
+
+    // Parses a width or precision, which is either a literal integer or an
+    // argument followed by `$`. Returns whether there was one.
+    fn count(&mut self) -> bool {
+        let count_start = self.pos;
+        let kind = if let Some(index) = self.integer() {
+            if self.peek() != Some('$') {
+                return true;
+            }
+            ArgumentKind::Index(index)
+        } else if let Some(name) = self.identifier() {
+            if self.peek() != Some('$') {
+                // Not a count after all but the format trait.
+                self.pos = count_start;
+                return false;
+            }
+            ArgumentKind::Name(name)
+        } else {
+            return false;
+        };
+        self.arguments.push(Argument {
+            kind,
+            syntax: Syntax::Explicit,
+            range: self.range(count_start, self.pos),
+            placeholder: 0..0,
+            format_trait: None,
+        });
+        self.pos += 1;
+        true
+    }
+
+    fn next_implicit(&mut self) -> ArgumentKind {
+        let index = self.implicit;
+        self.implicit += 1;
+        ArgumentKind::Index(index)
+    }
+
+    fn integer(&mut self) -> Option<usize> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        if self.pos == start {
+            return None;
+        }
+        let digits: String = self.chars[start..self.pos].iter().map(|c| c.1).collect();
+        Some(digits.parse().unwrap_or(usize::MAX))
+    }
+
+    fn identifier(&mut self) -> Option<String> {
+        if !self.peek().is_some_and(|c| c.is_alphabetic() || c == '_') {
+            return None;
+        }
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_alphanumeric() || c == '_') {
+            self.pos += 1;
+        }
+        Some(self.chars[start..self.pos].iter().map(|c| c.1).collect())
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.peek_nth(0)
+    }
+
+    fn peek_nth(&self, n: usize) -> Option<char> {
+        self.chars.get(self.pos + n).map(|c| c.1)
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        Some(c)
+    }
+
+    // Byte range in the format string covering chars[start..end].
+    fn range(&self, start: usize, end: usize) -> Range<usize> {
+        let offset = |i: usize| self.chars.get(i).map_or(self.len, |c| c.0);
+        offset(start)..offset(end)
+    }
+}
+
+// Narrows the span to the offending characters where the compiler allows it,
+// which requires the literal to be written without escapes so that offsets in
+// the value match offsets in the source. Otherwise the whole literal is used.
+fn span(lit: &LitStr, value: &str, range: Range<usize>) -> Span {
+    let repr = lit.token().to_string();
+    if let (Some(open), Some(close)) = (repr.find('"'), repr.rfind('"')) {
+        if open < close && &repr[open + 1..close] == value {
+            let start = open + 1 + range.start;
+            let end = open + 1 + range.end.max(range.start + 1).min(value.len());
+            if let Some(span) = lit.token().subspan(start..end) {
+                return span;
+            }
+        }
+    }
+    lit.span()
+}
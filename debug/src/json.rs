@@ -0,0 +1,272 @@
+use crate::attr::Extra;
+use crate::{Style, Variant};
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::ext::IdentExt;
+use syn::{DeriveInput, Generics};
+
+// #[debug(json)] generates `to_debug_json(&self) -> String`, rendering the
+// value as JSON for log pipelines that can't parse Debug output.
+//
+// Structs with named fields become objects and tuple structs become arrays,
+// while enum variants are wrapped in an object keyed by the variant name, or
+// are just the name for a unit variant. Numbers, bools, strings and chars, as
+// well as options of them, are written natively; any other value is written
+// as a string holding its Debug output. Fields are otherwise treated as the
+// Debug impl treats them: skipped fields are left out, names follow
+// #[debug(rename)], and fields with a format string, redaction and so on are
+// written as the string they would print as. A flattened field is written
+// whole under its own name, and a #[debug(transparent)] struct as its field.
+pub fn to_debug_json(
+    input: &DeriveInput,
+    variants: &[Variant],
+    extras: &[Extra],
+    transparent: bool,
+    generics: &Generics,
+    support: &TokenStream2,
+) -> TokenStream2 {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let arms = variants.iter().map(|variant| {
+        let pattern = variant.pattern();
+        let copies = variant.copies(quote!(self));
+        let named = match variant.style {
+            Style::Named => true,
+            Style::Tuple => false,
+            Style::Unit => {
+                let name = variant.ident.unwrap_or(name).unraw().to_string();
+                return quote! {
+                    #pattern => __json_str(#name, &mut __out),
+                };
+            }
+        };
+
+        let entries = variant
+            .fields
+            .iter()
+            .filter(|field| !field.attrs.skip)
+            .map(|field| {
+                let binding = &field.binding;
+                let key = if named {
+                    let key = field.name();
+                    quote!(::std::option::Option::Some(#key))
+                } else {
+                    quote!(::std::option::Option::None)
+                };
+                let value = if field.plain() || field.attrs.flatten {
+                    quote!((&__Json(#binding)).__json(&mut __out);)
+                } else {
+                    let value = field.value();
+                    quote!(__json_str(&::std::format!("{:?}", #value), &mut __out);)
+                };
+                if transparent {
+                    return value;
+                }
+                let entry = quote! {
+                    __json_entry(&mut __out, &mut __first, #key);
+                    #value
+                };
+                match &field.attrs.skip_if {
+                    Some(skip_if) => quote! {
+                        if !#skip_if(#binding) {
+                            #entry
+                        }
+                    },
+                    None => entry,
+                }
+            });
+        let extras = extras.iter().map(|extra| {
+            let Extra { name, expr } = extra;
+            quote! {
+                __json_entry(&mut __out, &mut __first, ::std::option::Option::Some(#name));
+                (&__Json(&(#expr))).__json(&mut __out);
+            }
+        });
+        let entries: Vec<_> = entries.chain(extras).collect();
+        if transparent {
+            return quote! {
+                #pattern => {
+                    #copies
+                    #(#entries)*
+                }
+            };
+        }
+
+        let (open, close) = if named { ('{', '}') } else { ('[', ']') };
+        let first = if entries.is_empty() {
+            None
+        } else {
+            Some(quote!(let mut __first = true;))
+        };
+        let (tag, untag) = match variant.ident {
+            Some(ident) => {
+                let ident = ident.unraw().to_string();
+                (
+                    Some(quote! {
+                        __out.push('{');
+                        __json_str(#ident, &mut __out);
+                        __out.push(':');
+                    }),
+                    Some(quote!(__out.push('}');)),
+                )
+            }
+            None => (None, None),
+        };
+        quote! {
+            #pattern => {
+                #copies
+                #tag
+                __out.push(#open);
+                #first
+                #(#entries)*
+                __out.push(#close);
+                #untag
+            }
+        }
+    });
+    let body = if variants.is_empty() {
+        quote!(match *self {})
+    } else {
+        quote! {
+            let mut __out = ::std::string::String::new();
+            match self {
+                #(#arms)*
+            }
+            __out
+        }
+    };
+
+    let json = support_json();
+    quote! {
+        impl #impl_generics #name #ty_generics #where_clause {
+            /// Renders the value as JSON, with the fields that the Debug impl
+            /// prints.
+            #[allow(dead_code)]
+            pub fn to_debug_json(&self) -> ::std::string::String {
+                #support
+                #json
+                #body
+            }
+        }
+    }
+}
+
+// `(&__Json(value)).__json(out)` writes a value natively where __JsonNative
+// is implemented for it, and as a string of its Debug output otherwise: method
+// resolution tries `&__Json<T>` as the receiver, matching __JsonNative, before
+// the autoref `&&__Json<T>` matching the Debug fallback. This is resolved
+// in the generated impl, so a field of generic type is written through Debug.
+fn support_json() -> TokenStream2 {
+    let numbers = [
+        "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64", "u128", "usize",
+    ]
+    .iter()
+    .map(|ty| format_ident!("{}", ty));
+    let floats = ["f32", "f64"].iter().map(|ty| format_ident!("{}", ty));
+    let strings = [quote!(str), quote!(&'b str), quote!(::std::string::String)];
+
+    quote! {
+        struct __Json<'a, T: ?::std::marker::Sized>(&'a T);
+
+        trait __JsonNative {
+            fn __json(&self, out: &mut ::std::string::String);
+        }
+
+        trait __JsonDebug {
+            fn __json(&self, out: &mut ::std::string::String);
+        }
+
+        impl<'a, T: ?::std::marker::Sized + ::std::fmt::Debug> __JsonDebug for &__Json<'a, T> {
+            fn __json(&self, out: &mut ::std::string::String) {
+                __json_str(&::std::format!("{:?}", self.0), out);
+            }
+        }
+
+        #(
+            impl<'a> __JsonNative for __Json<'a, #numbers> {
+                fn __json(&self, out: &mut ::std::string::String) {
+                    *out += &::std::string::ToString::to_string(self.0);
+                }
+            }
+        )*
+
+        // JSON has no representation of NaN or infinity.
+        #(
+            impl<'a> __JsonNative for __Json<'a, #floats> {
+                fn __json(&self, out: &mut ::std::string::String) {
+                    if self.0.is_finite() {
+                        *out += &::std::string::ToString::to_string(self.0);
+                    } else {
+                        *out += "null";
+                    }
+                }
+            }
+        )*
+
+        impl<'a> __JsonNative for __Json<'a, bool> {
+            fn __json(&self, out: &mut ::std::string::String) {
+                *out += if *self.0 { "true" } else { "false" };
+            }
+        }
+
+        impl<'a> __JsonNative for __Json<'a, char> {
+            fn __json(&self, out: &mut ::std::string::String) {
+                __json_str(self.0.encode_utf8(&mut [0; 4]), out);
+            }
+        }
+
+        #(
+            impl<'a, 'b> __JsonNative for __Json<'a, #strings> {
+                fn __json(&self, out: &mut ::std::string::String) {
+                    __json_str(self.0, out);
+                }
+            }
+        )*
+
+        impl<'a, T> __JsonNative for __Json<'a, ::std::option::Option<T>>
+        where
+            for<'b> __Json<'b, T>: __JsonNative,
+        {
+            fn __json(&self, out: &mut ::std::string::String) {
+                match self.0 {
+                    ::std::option::Option::Some(value) => __JsonNative::__json(&__Json(value), out),
+                    ::std::option::Option::None => *out += "null",
+                }
+            }
+        }
+
+        // Separator and, in an object, key of the next entry.
+        fn __json_entry(
+            out: &mut ::std::string::String,
+            first: &mut bool,
+            key: ::std::option::Option<&str>,
+        ) {
+            if !::std::mem::replace(first, false) {
+                out.push(',');
+            }
+            if let ::std::option::Option::Some(key) = key {
+                __json_str(key, out);
+                out.push(':');
+            }
+        }
+
+        fn __json_str(value: &str, out: &mut ::std::string::String) {
+            out.push('"');
+            for c in value.chars() {
+                match c {
+                    '"' => *out += "\\\"",
+                    '\\' => *out += "\\\\",
+                    '\n' => *out += "\\n",
+                    '\r' => *out += "\\r",
+                    '\t' => *out += "\\t",
+                    c if c.is_control() => {
+                        *out += &::std::format!("\\u{:04x}", c as u32);
+                    }
+                    c => out.push(c),
+                }
+            }
+            out.push('"');
+        }
+    }
+}
@@ -1,10 +1,1181 @@
 extern crate proc_macro;
 
+mod attr;
+mod bound;
+mod cmp;
+mod diff;
+mod display;
+mod fields;
+mod format;
+mod json;
+
+use crate::attr::{Attrs, Extra, FieldAttrs, Redact};
 use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote, quote_spanned};
+use syn::ext::IdentExt;
+use syn::spanned::Spanned;
+use syn::{
+    parse_macro_input, parse_quote, Data, DeriveInput, Field, Fields, GenericArgument, Ident,
+    Member, PathArguments, Type, WherePredicate,
+};
 
-#[proc_macro_derive(CustomDebug)]
+#[proc_macro_derive(CustomDebug, attributes(debug))]
 pub fn derive(input: TokenStream) -> TokenStream {
-    let _ = input;
+    let input = parse_macro_input!(input as DeriveInput);
+
+    expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[proc_macro_derive(CustomDisplay, attributes(display))]
+pub fn derive_display(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    display::expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[proc_macro_derive(CustomPartialEq, attributes(eq))]
+pub fn derive_partial_eq(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    cmp::partial_eq(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[proc_macro_derive(CustomHash, attributes(hash))]
+pub fn derive_hash(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    cmp::hash(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[proc_macro]
+pub fn assert_debug_eq(input: TokenStream) -> TokenStream {
+    diff::assert_debug_eq(input.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let container = attr::container(&input.attrs)?;
+
+    let mut variants = match &input.data {
+        Data::Struct(data) => vec![Variant::parse(None, &data.fields)?],
+        Data::Enum(data) => data
+            .variants
+            .iter()
+            .map(|variant| Variant::parse(Some(&variant.ident), &variant.fields))
+            .collect::<syn::Result<_>>()?,
+        // Which field of a union is valid is unknown, so none are printed.
+        Data::Union(_) => Vec::new(),
+    };
+
+    if attr::packed(&input.attrs)? {
+        // Only structs can be packed.
+        for variant in &mut variants {
+            variant.packed = true;
+        }
+    }
+
+    // An opaque type prints only the fields it shows, as if the rest were
+    // skipped, and so also places no bounds on them.
+    match &container.opaque {
+        Some(opaque) => {
+            if container.transparent || container.with.is_some() {
+                return Err(syn::Error::new_spanned(
+                    opaque,
+                    "`opaque` cannot be combined with `transparent` or `with`",
+                ));
+            }
+            for field in variants.iter_mut().flat_map(|variant| &mut variant.fields) {
+                if field.attrs.show.is_none() {
+                    field.attrs.skip = true;
+                }
+            }
+        }
+        None => {
+            let shown = variants
+                .iter()
+                .flat_map(|variant| &variant.fields)
+                .filter_map(|field| field.attrs.show.as_ref());
+            let errors = shown.map(|show| {
+                syn::Error::new_spanned(show, "`show` is only supported on a #[debug(opaque)] type")
+            });
+            if let Some(err) = combine(errors) {
+                return Err(err);
+            }
+        }
+    }
+
+    if container.deny_secrets {
+        deny_secrets(&variants)?;
+    }
+    if container.sorted {
+        // Only fields that are recognizably a HashMap or HashSet and aren't
+        // already printed some other way.
+        for field in variants.iter_mut().flat_map(|variant| &mut variant.fields) {
+            if field.plain() && !field.attrs.flatten && is_hash_collection(field.ty) {
+                field.attrs.sorted = true;
+            }
+        }
+    }
+
+    // A type-level #[debug(with = "...")] prints the value without looking at
+    // any of the fields.
+    let fields_printed = container.with.is_none();
+    let printed = || {
+        variants
+            .iter()
+            .filter(|_| fields_printed)
+            .flat_map(|variant| &variant.fields)
+            .filter(|field| !field.attrs.skip)
+    };
+
+    let name = &input.ident;
+    let mut generics = input.generics.clone();
+    // Fields with their own #[debug(bound = "...")] take part in neither
+    // container-level nor inferred bounds; their bounds are used as written.
+    let inferred = || printed().filter(|field| field.attrs.bound.is_none());
+    let bounds: Vec<WherePredicate> = match &container.bound {
+        Some(bound) => bound.iter().cloned().collect(),
+        None => {
+            let mut bounds = bound::infer(
+                &input.generics,
+                inferred()
+                    .filter(|field| field.uses_debug())
+                    .map(|field| field.ty),
+                &parse_quote!(::std::fmt::Debug),
+            );
+            bounds.extend(bound::infer(
+                &input.generics,
+                inferred()
+                    .filter(|field| field.attrs.redact == Some(Redact::Hash))
+                    .map(|field| field.ty),
+                &parse_quote!(::std::hash::Hash),
+            ));
+            bounds.extend(bound::infer(
+                &input.generics,
+                inferred()
+                    .filter(|field| field.attrs.bytes)
+                    .map(|field| field.ty),
+                &parse_quote!(::std::convert::AsRef<[u8]>),
+            ));
+            bounds.extend(bound::infer(
+                &input.generics,
+                inferred()
+                    .filter(|field| field.attrs.sorted)
+                    .filter_map(|field| sort_key(field.ty)),
+                &parse_quote!(::std::cmp::Ord),
+            ));
+            // Fields of a packed struct are copied out before printing.
+            bounds.extend(bound::infer(
+                &input.generics,
+                inferred()
+                    .filter(|_| variants.iter().any(|variant| variant.packed))
+                    .map(|field| field.ty),
+                &parse_quote!(::std::marker::Copy),
+            ));
+            bounds
+        }
+    };
+    let field_bounds = printed()
+        .filter_map(|field| field.attrs.bound.as_ref())
+        .flat_map(|bound| bound.iter().cloned());
+    let where_clause = generics.make_where_clause();
+    where_clause.predicates.extend(bounds);
+    where_clause.predicates.extend(field_bounds);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    if let Some(extra) = container.extras.first() {
+        let named = match (&input.data, variants.as_slice()) {
+            (Data::Struct(_), [variant]) => matches!(variant.style, Style::Named),
+            _ => false,
+        };
+        if !named || container.transparent || container.with.is_some() {
+            return Err(syn::Error::new_spanned(
+                &extra.name,
+                "`extra` is only supported on structs with named fields printed by CustomDebug",
+            ));
+        }
+    }
+
+    if let Some(key) = &container.json {
+        if container.with.is_some() || matches!(input.data, Data::Union(_)) {
+            return Err(syn::Error::new_spanned(
+                key,
+                "`json` is only supported on structs and enums printed by CustomDebug",
+            ));
+        }
+    }
+
+    let body = if let Some(with) = &container.with {
+        quote!(#with(self, f))
+    } else if let Data::Union(_) = &input.data {
+        let name = name.unraw().to_string();
+        quote!(f.debug_struct(#name).finish_non_exhaustive())
+    } else if container.transparent {
+        transparent(&variants)?
+    } else if variants.is_empty() {
+        quote!(match *self {})
+    } else {
+        let arms = variants
+            .iter()
+            .map(|variant| variant.arm(name, &container.extras));
+        quote! {
+            match self {
+                #(#arms)*
+            }
+        }
+    };
+
+    let with = printed()
+        .any(|field| field.attrs.with.is_some())
+        .then(debug_with);
+    let hash = printed()
+        .any(|field| field.attrs.redact == Some(Redact::Hash))
+        .then(redact_hash);
+    let truncate = printed()
+        .any(|field| field.attrs.truncate.is_some() && !field.attrs.bytes)
+        .then(debug_truncate);
+    let bytes = printed().any(|field| field.attrs.bytes).then(debug_bytes);
+    let sorted = printed().any(|field| field.attrs.sorted).then(debug_sorted);
+    let packed = variants.iter().any(|variant| variant.packed).then(|| {
+        assert_packed(
+            printed().map(|field| field.ty),
+            "printed by CustomDebug",
+            "debug",
+        )
+    });
+    let support = quote! {
+        #with
+        #hash
+        #truncate
+        #bytes
+        #sorted
+    };
+
+    // Structs with named fields can be flattened into a parent, and are
+    // compared field by field by assert_debug_eq!.
+    let flatten = match (&input.data, variants.as_slice()) {
+        (
+            Data::Struct(_),
+            [variant @ Variant {
+                style: Style::Named,
+                ..
+            }],
+        ) if container.with.is_none() => {
+            let body = variant.flatten(&container.extras);
+            let diff = diff::methods(variant, &support);
+            Some(quote! {
+                impl #impl_generics #name #ty_generics #where_clause {
+                    #[doc(hidden)]
+                    #[allow(dead_code)]
+                    pub fn __debug_flatten(
+                        &self,
+                        __prefix: &str,
+                        __debug: &mut ::std::fmt::DebugStruct,
+                    ) -> bool {
+                        #support
+                        #body
+                    }
+
+                    #diff
+                }
+            })
+        }
+        _ => None,
+    };
+
+    // Fields of a packed struct can't be borrowed.
+    let fields = match &input.data {
+        Data::Struct(_) | Data::Enum(_)
+            if container.with.is_none()
+                && !variants.is_empty()
+                && !variants.iter().any(|variant| variant.packed) =>
+        {
+            Some(fields::debug_fields(input, &variants, &generics, &support))
+        }
+        _ => None,
+    };
+
+    let depth = container.max_depth.map(max_depth);
+
+    let json = container.json.as_ref().map(|_| {
+        json::to_debug_json(
+            input,
+            &variants,
+            &container.extras,
+            container.transparent,
+            &generics,
+            &support,
+        )
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::std::fmt::Debug for #name #ty_generics #where_clause {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                #packed
+                #support
+                #depth
+                #body
+            }
+        }
+
+        #flatten
+
+        #fields
+
+        #json
+    })
+}
+
+// Adapter giving a field formatted by #[debug(with = "...")] a Debug impl that
+// calls the user's function.
+fn debug_with() -> TokenStream2 {
+    quote! {
+        struct __DebugWith<'a, T: ?::std::marker::Sized>(
+            &'a T,
+            fn(&T, &mut ::std::fmt::Formatter) -> ::std::fmt::Result,
+        );
+
+        impl<'a, T: ?::std::marker::Sized> ::std::fmt::Debug for __DebugWith<'a, T> {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                (self.1)(self.0, f)
+            }
+        }
+    }
+}
+
+// Short digest for #[debug(redact = "hash")]. FNV-1a rather than the standard
+// library's DefaultHasher, whose algorithm is not guaranteed to stay the same
+// between Rust releases, so digests in old logs remain comparable.
+fn redact_hash() -> TokenStream2 {
+    quote! {
+        struct __Fnv(u64);
+
+        impl ::std::hash::Hasher for __Fnv {
+            fn finish(&self) -> u64 {
+                self.0
+            }
+
+            fn write(&mut self, bytes: &[u8]) {
+                for byte in bytes {
+                    self.0 = (self.0 ^ *byte as u64).wrapping_mul(0x100000001b3);
+                }
+            }
+        }
+
+        fn __redact_hash<T: ?::std::marker::Sized + ::std::hash::Hash>(value: &T) -> u32 {
+            let mut hasher = __Fnv(0xcbf29ce484222325);
+            ::std::hash::Hash::hash(value, &mut hasher);
+            (::std::hash::Hasher::finish(&hasher) >> 32) as u32
+        }
+    }
+}
+
+// Adapter for #[debug(truncate = N)], printing at most N elements of a
+// collection or characters of a string followed by how many were left out.
+fn debug_truncate() -> TokenStream2 {
+    quote! {
+        struct __DebugTruncate<'a, T: ?::std::marker::Sized>(&'a T, usize);
+
+        impl<'a, T> ::std::fmt::Debug for __DebugTruncate<'a, T>
+        where
+            T: ?::std::marker::Sized + __Truncate,
+        {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                __Truncate::fmt(self.0, self.1, f)
+            }
+        }
+
+        trait __Truncate {
+            fn fmt(&self, limit: usize, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result;
+        }
+
+        // Formats the first `limit` of `len` entries as a list, or as a set
+        // for unordered collections and maps.
+        fn __truncate<I>(
+            f: &mut ::std::fmt::Formatter,
+            set: bool,
+            entries: I,
+            len: usize,
+            limit: usize,
+        ) -> ::std::fmt::Result
+        where
+            I: ::std::iter::Iterator,
+            I::Item: ::std::fmt::Debug,
+        {
+            let more = len.saturating_sub(limit);
+            let more = ::std::format_args!("... ({} more)", more);
+            let more = if len > limit {
+                ::std::option::Option::Some(&more)
+            } else {
+                ::std::option::Option::None
+            };
+            if set {
+                f.debug_set()
+                    .entries(entries.take(limit))
+                    .entries(more)
+                    .finish()
+            } else {
+                f.debug_list()
+                    .entries(entries.take(limit))
+                    .entries(more)
+                    .finish()
+            }
+        }
+
+        // One `key: value` entry of a truncated map.
+        struct __Entry<K, V>(K, V);
+
+        impl<K: ::std::fmt::Debug, V: ::std::fmt::Debug> ::std::fmt::Debug for __Entry<K, V> {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                ::std::fmt::Debug::fmt(&self.0, f)?;
+                f.write_str(": ")?;
+                ::std::fmt::Debug::fmt(&self.1, f)
+            }
+        }
+
+        impl __Truncate for str {
+            fn fmt(&self, limit: usize, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                match self.char_indices().nth(limit) {
+                    ::std::option::Option::Some((end, _)) => {
+                        ::std::fmt::Debug::fmt(&self[..end], f)?;
+                        ::std::write!(f, "... ({} more)", self[end..].chars().count())
+                    }
+                    ::std::option::Option::None => ::std::fmt::Debug::fmt(self, f),
+                }
+            }
+        }
+
+        impl __Truncate for ::std::string::String {
+            fn fmt(&self, limit: usize, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                __Truncate::fmt(self.as_str(), limit, f)
+            }
+        }
+
+        impl<T: ::std::fmt::Debug> __Truncate for [T] {
+            fn fmt(&self, limit: usize, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                __truncate(f, false, self.iter(), self.len(), limit)
+            }
+        }
+
+        impl<T: ::std::fmt::Debug, const N: usize> __Truncate for [T; N] {
+            fn fmt(&self, limit: usize, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                __truncate(f, false, self.iter(), N, limit)
+            }
+        }
+
+        impl<T: ::std::fmt::Debug> __Truncate for ::std::vec::Vec<T> {
+            fn fmt(&self, limit: usize, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                __truncate(f, false, self.iter(), self.len(), limit)
+            }
+        }
+
+        impl<T: ::std::fmt::Debug> __Truncate for ::std::collections::VecDeque<T> {
+            fn fmt(&self, limit: usize, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                __truncate(f, false, self.iter(), self.len(), limit)
+            }
+        }
+
+        impl<T: ::std::fmt::Debug> __Truncate for ::std::collections::BTreeSet<T> {
+            fn fmt(&self, limit: usize, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                __truncate(f, true, self.iter(), self.len(), limit)
+            }
+        }
+
+        impl<T: ::std::fmt::Debug, S> __Truncate for ::std::collections::HashSet<T, S> {
+            fn fmt(&self, limit: usize, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                __truncate(f, true, self.iter(), self.len(), limit)
+            }
+        }
+
+        impl<K: ::std::fmt::Debug, V: ::std::fmt::Debug> __Truncate
+            for ::std::collections::BTreeMap<K, V>
+        {
+            fn fmt(&self, limit: usize, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                let entries = self.iter().map(|(k, v)| __Entry(k, v));
+                __truncate(f, true, entries, self.len(), limit)
+            }
+        }
+
+        impl<K: ::std::fmt::Debug, V: ::std::fmt::Debug, S> __Truncate
+            for ::std::collections::HashMap<K, V, S>
+        {
+            fn fmt(&self, limit: usize, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                let entries = self.iter().map(|(k, v)| __Entry(k, v));
+                __truncate(f, true, entries, self.len(), limit)
+            }
+        }
+
+        impl<'a, T: ?::std::marker::Sized + __Truncate> __Truncate for &'a T {
+            fn fmt(&self, limit: usize, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                __Truncate::fmt(*self, limit, f)
+            }
+        }
+    }
+}
+
+// Adapter for #[debug(bytes)], printing a byte buffer as hex in place of a
+// list of decimal numbers, optionally truncated.
+fn debug_bytes() -> TokenStream2 {
+    quote! {
+        struct __DebugBytes<'a>(&'a [u8], ::std::option::Option<usize>);
+
+        impl<'a> ::std::fmt::Debug for __DebugBytes<'a> {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                let limit = self.1.unwrap_or(self.0.len()).min(self.0.len());
+                f.write_str("[")?;
+                for byte in &self.0[..limit] {
+                    ::std::write!(f, "{:02x}", byte)?;
+                }
+                if limit < self.0.len() {
+                    ::std::write!(f, "... ({} more)", self.0.len() - limit)?;
+                }
+                f.write_str("]")
+            }
+        }
+    }
+}
+
+// Adapter for #[debug(sorted)], printing the entries of a map or set ordered
+// by key so that output doesn't depend on the hasher's random state.
+fn debug_sorted() -> TokenStream2 {
+    quote! {
+        struct __DebugSorted<'a, T: ?::std::marker::Sized>(&'a T);
+
+        impl<'a, T> ::std::fmt::Debug for __DebugSorted<'a, T>
+        where
+            T: ?::std::marker::Sized + __Sorted,
+        {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                __Sorted::fmt(self.0, f)
+            }
+        }
+
+        trait __Sorted {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result;
+        }
+
+        impl<K, V, S> __Sorted for ::std::collections::HashMap<K, V, S>
+        where
+            K: ::std::cmp::Ord + ::std::fmt::Debug,
+            V: ::std::fmt::Debug,
+        {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                let mut entries: ::std::vec::Vec<_> = self.iter().collect();
+                entries.sort_by(|a, b| ::std::cmp::Ord::cmp(a.0, b.0));
+                f.debug_map().entries(entries).finish()
+            }
+        }
+
+        impl<T, S> __Sorted for ::std::collections::HashSet<T, S>
+        where
+            T: ::std::cmp::Ord + ::std::fmt::Debug,
+        {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                let mut entries: ::std::vec::Vec<_> = self.iter().collect();
+                entries.sort();
+                f.debug_set().entries(entries).finish()
+            }
+        }
+
+        // Already in order.
+        impl<K: ::std::fmt::Debug, V: ::std::fmt::Debug> __Sorted
+            for ::std::collections::BTreeMap<K, V>
+        {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                ::std::fmt::Debug::fmt(self, f)
+            }
+        }
+
+        impl<T: ::std::fmt::Debug> __Sorted for ::std::collections::BTreeSet<T> {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                ::std::fmt::Debug::fmt(self, f)
+            }
+        }
+
+        impl<'a, T: ?::std::marker::Sized + __Sorted> __Sorted for &'a T {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                __Sorted::fmt(*self, f)
+            }
+        }
+    }
+}
+
+// Whether a field type is spelled as a HashMap or HashSet, for container-level
+// #[debug(sorted)].
+fn is_hash_collection(ty: &Type) -> bool {
+    match ty {
+        Type::Path(ty) if ty.qself.is_none() => {
+            let ident = &ty.path.segments.last().unwrap().ident;
+            ident == "HashMap" || ident == "HashSet"
+        }
+        _ => false,
+    }
+}
+
+// Key type of a sorted map or set, the first type argument as in `HashMap<K,
+// V>`, which needs an Ord bound. References are looked through.
+fn sort_key(ty: &Type) -> Option<&Type> {
+    match ty {
+        Type::Reference(ty) => sort_key(&ty.elem),
+        Type::Path(ty) => match &ty.path.segments.last()?.arguments {
+            PathArguments::AngleBracketed(args) => args.args.iter().find_map(|arg| match arg {
+                GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            }),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// Printing a field of a #[repr(packed)] struct by reference could create an
+// unaligned reference, so fields are copied into locals first, with an
+// unaligned read. This checks that they are Copy, without which duplicating
+// their bits would be unsound.
+//
+// Each check is spanned at the field's type, which the error then points at.
+// That includes the blanket impl, so the note explaining why the type doesn't
+// implement __PackedField points at the field as well, instead of at the
+// derive.
+//
+// `usage` completes the error message, like "printed by CustomDebug", and
+// `name` is the name of the derive's attributes, for the hint to skip.
+fn assert_packed<'a>(
+    types: impl Iterator<Item = &'a Type>,
+    usage: &str,
+    name: &str,
+) -> TokenStream2 {
+    let message = format!(
+        "`{{Self}}` is not Copy, which fields of a packed struct must be to be {}",
+        usage,
+    );
+    let note = format!("skip the field with #[{}(skip)]", name);
+    let asserts = types.map(|ty| {
+        quote_spanned! {ty.span()=>
+            {
+                #[diagnostic::on_unimplemented(
+                    message = #message,
+                    label = "not Copy",
+                    note = #note
+                )]
+                trait __PackedField {}
+
+                impl<T: ::std::marker::Copy> __PackedField for T {}
+
+                fn __assert_packed<T: __PackedField>() {}
+
+                __assert_packed::<#ty>();
+            }
+        }
+    });
+    quote!(#(#asserts)*)
+}
+
+// #[debug(deny_secrets)] rejects fields whose name suggests they hold a
+// credential unless they are redacted or skipped.
+fn deny_secrets(variants: &[Variant]) -> syn::Result<()> {
+    const SECRETS: &[&str] = &["password", "token", "secret"];
+
+    let mut errors = Vec::new();
+    for field in variants.iter().flat_map(|variant| &variant.fields) {
+        let ident = match &field.member {
+            Member::Named(ident) => ident,
+            Member::Unnamed(_) => continue,
+        };
+        if field.attrs.skip || field.attrs.redact.is_some() {
+            continue;
+        }
+        let name = ident.unraw().to_string().to_lowercase();
+        if SECRETS.iter().any(|secret| name.contains(secret)) {
+            errors.push(syn::Error::new_spanned(
+                ident,
+                format!(
+                    "field `{}` may hold a secret; add #[debug(redact)] or #[debug(skip)]",
+                    ident.unraw(),
+                ),
+            ));
+        }
+    }
+
+    match combine(errors) {
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
+}
+
+// #[debug(transparent)] forwards to the Debug impl of the single field, so that
+// a newtype like `UserId(42)` prints as `42`. Skipped fields, such as a
+// PhantomData marker, don't count.
+fn transparent(variants: &[Variant]) -> syn::Result<TokenStream2> {
+    let field = match variants {
+        [variant] if variant.ident.is_none() => {
+            let mut fields = variant.fields.iter().filter(|field| !field.attrs.skip);
+            match (fields.next(), fields.next()) {
+                (Some(field), None) => Some((variant, field)),
+                _ => None,
+            }
+        }
+        _ => None,
+    };
+    let (variant, field) = match field {
+        Some(field) => field,
+        None => {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                "#[debug(transparent)] requires a struct with exactly one field",
+            ))
+        }
+    };
+    if let Some(skip_if) = &field.attrs.skip_if {
+        return Err(syn::Error::new_spanned(
+            skip_if,
+            "`skip_if` cannot be used on the field of a transparent struct",
+        ));
+    }
+
+    let pattern = variant.pattern();
+    let copies = variant.copies(quote!(self));
+    let value = field.value();
+    Ok(quote! {
+        match self {
+            #pattern => {
+                #copies
+                ::std::fmt::Debug::fmt(#value, f)
+            }
+        }
+    })
+}
+
+// #[debug(max_depth = N)] prints `...` in place of a value nested inside N
+// others of the same type, so that deep or cyclic structures print a bounded
+// amount of output. Each type counts its own depth in a thread-local, which
+// the guard restores even when a nested Debug impl fails or panics.
+fn max_depth(max_depth: usize) -> TokenStream2 {
+    quote! {
+        ::std::thread_local! {
+            static __DEPTH: ::std::cell::Cell<usize> = const { ::std::cell::Cell::new(0) };
+        }
+        let __depth = __DEPTH.with(::std::cell::Cell::get);
+        if __depth >= #max_depth {
+            return f.write_str("...");
+        }
+        struct __DepthGuard(usize);
+        impl ::std::ops::Drop for __DepthGuard {
+            fn drop(&mut self) {
+                __DEPTH.with(|depth| depth.set(self.0));
+            }
+        }
+        let _guard = __DepthGuard(__depth);
+        __DEPTH.with(|depth| depth.set(__depth + 1));
+    }
+}
+
+// A struct, or one variant of an enum, with field attributes `A` of the
+// derive being expanded.
+struct Variant<'a, A = FieldAttrs> {
+    ident: Option<&'a Ident>,
+    style: Style,
+    fields: Vec<DebugField<'a, A>>,
+    // Fields of a #[repr(packed)] struct are copied rather than borrowed.
+    packed: bool,
+}
+
+enum Style {
+    Named,
+    Tuple,
+    Unit,
+}
+
+struct DebugField<'a, A = FieldAttrs> {
+    member: Member,
+    ty: &'a Type,
+    attrs: A,
+    // Name the field is bound to in the match arm.
+    binding: Ident,
+}
+
+impl<'a> Variant<'a> {
+    fn parse(ident: Option<&'a Ident>, fields: &'a Fields) -> syn::Result<Self> {
+        Variant::parse_with(ident, fields, |field, style| {
+            let attrs = attr::field(&field.attrs)?;
+            if let (Style::Tuple, true) = (style, attrs.flatten) {
+                return Err(syn::Error::new_spanned(
+                    &field.ty,
+                    "`flatten` is only supported on named fields",
+                ));
+            }
+            if let (Style::Tuple, Some(rename)) = (style, &attrs.rename) {
+                return Err(syn::Error::new_spanned(
+                    rename,
+                    "`rename` is only supported on named fields",
+                ));
+            }
+            Ok(attrs)
+        })
+    }
+}
+
+impl<'a, A: Attrs> Variant<'a, A> {
+    // Parses the attributes of each field with `attrs`.
+    fn parse_with(
+        ident: Option<&'a Ident>,
+        fields: &'a Fields,
+        mut attrs: impl FnMut(&'a Field, &Style) -> syn::Result<A>,
+    ) -> syn::Result<Self> {
+        let style = match fields {
+            Fields::Named(_) => Style::Named,
+            Fields::Unnamed(_) => Style::Tuple,
+            Fields::Unit => Style::Unit,
+        };
+        // Errors from all fields are reported together, since a refactor will
+        // often break several format strings at once.
+        let mut errors = Vec::new();
+        let fields = fields
+            .iter()
+            .enumerate()
+            .filter_map(|(i, field)| {
+                let attrs = match attrs(field, &style) {
+                    Ok(attrs) => attrs,
+                    Err(err) => {
+                        errors.push(err);
+                        return None;
+                    }
+                };
+                Some(DebugField {
+                    member: match &field.ident {
+                        Some(ident) => Member::Named(ident.clone()),
+                        None => Member::Unnamed(i.into()),
+                    },
+                    ty: &field.ty,
+                    attrs,
+                    binding: format_ident!("__self_{}", i),
+                })
+            })
+            .collect();
+        if let Some(err) = combine(errors) {
+            return Err(err);
+        }
+        Ok(Variant {
+            ident,
+            style,
+            fields,
+            packed: false,
+        })
+    }
+
+    // Binds every used field of the variant by reference. Braced patterns
+    // work for all three styles, with `0: __self_0` for tuple fields.
+    fn pattern(&self) -> TokenStream2 {
+        self.pattern_with(|field| field.binding.clone())
+    }
+
+    // Like `pattern`, with each field bound to the name given by `binding`.
+    fn pattern_with(&self, binding: impl Fn(&DebugField<A>) -> Ident) -> TokenStream2 {
+        let path = match self.ident {
+            Some(ident) => quote!(Self::#ident),
+            None => quote!(Self),
+        };
+        if self.packed {
+            return quote!(#path { .. });
+        }
+        let bindings = self.fields.iter().map(|field| {
+            let member = &field.member;
+            if field.attrs.skip() {
+                quote!(#member: _)
+            } else {
+                let binding = binding(field);
+                quote!(#member: #binding)
+            }
+        });
+        quote!(#path { #(#bindings),* })
+    }
+
+    // Statements binding the used fields of a packed struct to copies, as
+    // `pattern` does for other variants. `value` is the struct to copy from.
+    fn copies(&self, value: TokenStream2) -> TokenStream2 {
+        self.copies_with(value, |field| field.binding.clone())
+    }
+
+    fn copies_with(
+        &self,
+        value: TokenStream2,
+        binding: impl Fn(&DebugField<A>) -> Ident,
+    ) -> TokenStream2 {
+        if !self.packed {
+            return TokenStream2::new();
+        }
+        let copies = self
+            .fields
+            .iter()
+            .filter(|field| !field.attrs.skip())
+            .map(|field| {
+                let member = &field.member;
+                let binding = binding(field);
+                // SAFETY: the field is aligned by the read, and is Copy, as
+                // checked by `assert_packed` wherever a derive copies fields,
+                // so the bits can be duplicated.
+                quote! {
+                    let #binding = &unsafe {
+                        ::std::ptr::read_unaligned(::std::ptr::addr_of!(#value.#member))
+                    };
+                }
+            });
+        quote!(#(#copies)*)
+    }
+}
+
+impl<'a> Variant<'a> {
+    // `extras` are printed after the fields, for a struct with named fields.
+    fn arm(&self, container: &Ident, extras: &[Extra]) -> TokenStream2 {
+        let pattern = self.pattern();
+        let name = self.ident.unwrap_or(container).unraw().to_string();
+
+        let builder = match self.style {
+            Style::Named => quote!(f.debug_struct(#name)),
+            Style::Tuple => quote!(f.debug_tuple(#name)),
+            Style::Unit => {
+                return quote! {
+                    #pattern => f.write_str(#name),
+                };
+            }
+        };
+
+        // Output ends in `..` if any field was left out, either always for
+        // #[debug(skip)] or depending on the value for #[debug(skip_if)] and
+        // on what flattened fields left out.
+        let skipped = self.skipped();
+        let conditional = !skipped && self.conditional();
+        let entries = self
+            .fields
+            .iter()
+            .filter(|field| !field.attrs.skip)
+            .map(|field| field.entry(conditional, false));
+        let finish = if skipped {
+            quote!(__debug.finish_non_exhaustive())
+        } else if conditional {
+            quote! {
+                if __exhaustive {
+                    __debug.finish()
+                } else {
+                    __debug.finish_non_exhaustive()
+                }
+            }
+        } else {
+            quote!(__debug.finish())
+        };
+        let exhaustive = if conditional {
+            Some(quote!(let mut __exhaustive = true;))
+        } else {
+            None
+        };
+
+        let extras = extras.iter().map(|extra| {
+            let Extra { name, expr } = extra;
+            quote!(__debug.field(#name, &(#expr));)
+        });
+
+        let copies = self.copies(quote!(self));
+        quote! {
+            #pattern => {
+                #copies
+                let mut __debug = #builder;
+                #exhaustive
+                #(#entries)*
+                #(#extras)*
+                #finish
+            }
+        }
+    }
+
+    fn skipped(&self) -> bool {
+        self.fields.iter().any(|field| field.attrs.skip)
+    }
+
+    fn conditional(&self) -> bool {
+        self.fields
+            .iter()
+            .any(|field| field.attrs.skip_if.is_some() || field.attrs.flatten)
+    }
+
+    // Body of `__debug_flatten`, which adds the fields to a parent's
+    // `__debug` with their names prefixed and returns whether none were left
+    // out.
+    fn flatten(&self, extras: &[Extra]) -> TokenStream2 {
+        let pattern = self.pattern();
+        let skipped = self.skipped();
+        let conditional = !skipped && self.conditional();
+        let entries = self
+            .fields
+            .iter()
+            .filter(|field| !field.attrs.skip)
+            .map(|field| field.entry(conditional, true));
+        let (exhaustive, result) = if skipped {
+            (None, quote!(false))
+        } else if conditional {
+            (
+                Some(quote!(let mut __exhaustive = true;)),
+                quote!(__exhaustive),
+            )
+        } else {
+            (None, quote!(true))
+        };
+        let extras = extras.iter().map(|extra| {
+            let Extra { name, expr } = extra;
+            quote!(__debug.field(&::std::format!("{}{}", __prefix, #name), &(#expr));)
+        });
+
+        let copies = self.copies(quote!(self));
+        quote! {
+            let #pattern = self;
+            #copies
+            #exhaustive
+            #(#entries)*
+            #(#extras)*
+            #result
+        }
+    }
+}
+
+impl<'a> DebugField<'a> {
+    // Whether the field is printed through its own Debug impl.
+    fn uses_debug(&self) -> bool {
+        self.attrs.with.is_none() && self.attrs.redact.is_none() && !self.attrs.bytes
+    }
+
+    // Whether the field is printed by its Debug impl as is.
+    fn plain(&self) -> bool {
+        let attrs = &self.attrs;
+        attrs.format.is_none()
+            && attrs.with.is_none()
+            && attrs.redact.is_none()
+            && attrs.truncate.is_none()
+            && !attrs.bytes
+            && !attrs.sorted
+    }
+
+    // Name printed for a named field.
+    fn name(&self) -> String {
+        match &self.member {
+            Member::Named(ident) => match &self.attrs.rename {
+                Some(rename) => rename.value(),
+                None => ident.unraw().to_string(),
+            },
+            Member::Unnamed(_) => String::new(),
+        }
+    }
+
+    // Statement adding this field to `__debug`. When `track` is set, a field
+    // omitted by its skip_if predicate, or a flattened field that left some
+    // of its own fields out, clears `__exhaustive`. When `prefixed` is set,
+    // names are prepended with `__prefix` for flattening into a parent.
+    fn entry(&self, track: bool, prefixed: bool) -> TokenStream2 {
+        let binding = &self.binding;
+        let value = self.value();
+        let name = self.name();
+        let add = if self.attrs.flatten {
+            let prefix = match &self.attrs.prefix {
+                Some(prefix) => prefix.value(),
+                None => String::new(),
+            };
+            let (prefix, debug) = if prefixed {
+                (
+                    quote!(&::std::format!("{}{}", __prefix, #prefix)),
+                    quote!(__debug),
+                )
+            } else {
+                (quote!(#prefix), quote!(&mut __debug))
+            };
+            let flatten = quote!(#binding.__debug_flatten(#prefix, #debug));
+            if track {
+                quote! {
+                    if !#flatten {
+                        __exhaustive = false;
+                    }
+                }
+            } else {
+                quote!(#flatten;)
+            }
+        } else if let Member::Unnamed(_) = self.member {
+            quote!(__debug.field(#value);)
+        } else if prefixed {
+            quote!(__debug.field(&::std::format!("{}{}", __prefix, #name), #value);)
+        } else {
+            quote!(__debug.field(#name, #value);)
+        };
+        let skip_if = match &self.attrs.skip_if {
+            Some(skip_if) => skip_if,
+            None => return add,
+        };
+        if track {
+            quote! {
+                if #skip_if(#binding) {
+                    __exhaustive = false;
+                } else {
+                    #add
+                }
+            }
+        } else {
+            quote! {
+                if !#skip_if(#binding) {
+                    #add
+                }
+            }
+        }
+    }
+
+    // Expression of type `&impl Debug` rendering this field.
+    fn value(&self) -> TokenStream2 {
+        self.render(&self.binding)
+    }
+
+    // Like `value`, for the field bound to `binding`.
+    fn render(&self, binding: &Ident) -> TokenStream2 {
+        match self.attrs.redact {
+            Some(Redact::Full) => return quote!(&::std::format_args!("<redacted>")),
+            Some(Redact::Len) => {
+                return quote!(&::std::format_args!("<redacted len={}>", #binding.len()))
+            }
+            Some(Redact::Hash) => {
+                return quote!(&::std::format_args!("<redacted #{:08x}>", __redact_hash(#binding)))
+            }
+            None => {}
+        }
+        if let Some(with) = &self.attrs.with {
+            return quote!(&__DebugWith(#binding, #with));
+        }
+        if self.attrs.bytes {
+            let limit = match self.attrs.truncate {
+                Some(limit) => quote!(::std::option::Option::Some(#limit)),
+                None => quote!(::std::option::Option::None),
+            };
+            return quote!(&__DebugBytes(::std::convert::AsRef::<[u8]>::as_ref(#binding), #limit));
+        }
+        if let Some(limit) = self.attrs.truncate {
+            return quote!(&__DebugTruncate(#binding, #limit));
+        }
+        if self.attrs.sorted {
+            return quote!(&__DebugSorted(#binding));
+        }
+        match &self.attrs.format {
+            Some(format) => quote!(&::std::format_args!(#format, #binding)),
+            None => quote!(#binding),
+        }
+    }
+}
 
-    unimplemented!()
+fn combine(errors: impl IntoIterator<Item = syn::Error>) -> Option<syn::Error> {
+    errors.into_iter().reduce(|mut errors, err| {
+        errors.combine(err);
+        errors
+    })
 }
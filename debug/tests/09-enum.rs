@@ -0,0 +1,95 @@
+// Enums are supported too. Each variant is printed the same way as the
+// standard library's #[derive(Debug)] would print it: unit variants as just
+// their name, tuple variants through DebugTuple and struct-like variants
+// through DebugStruct. The #[debug = "..."] attribute applies to fields of any
+// variant, and #[debug(bound = "...")] may be placed on the enum.
+//
+//     impl Debug for State {
+//         fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+//             match self {
+//                 Self::Idle {} => f.write_str("Idle"),
+//                 Self::Running { 0: __self_0 } => f.debug_tuple("Running").field(__self_0).finish(),
+//                 ...
+//             }
+//         }
+//     }
+
+use derive_debug::CustomDebug;
+use std::fmt::Debug;
+
+#[derive(CustomDebug)]
+pub enum State<T> {
+    Idle,
+    Running(u32, T),
+    Failed {
+        code: i32,
+        #[debug = "0b{:08b}"]
+        flags: u8,
+    },
+    Empty(),
+}
+
+// The same enum with the standard derive, to compare output against.
+#[derive(Debug)]
+pub enum Expected<T> {
+    Idle,
+    Running(u32, T),
+    Failed { code: i32, flags: Binary },
+    Empty(),
+}
+
+pub struct Binary(u8);
+
+impl Debug for Binary {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "0b{:08b}", self.0)
+    }
+}
+
+pub trait Trait {
+    type Value;
+}
+
+#[derive(CustomDebug)]
+#[debug(bound = "T::Value: Debug")]
+pub enum Wrapper<T: Trait> {
+    Value(T::Value),
+    Nothing,
+}
+
+#[derive(CustomDebug)]
+pub enum Never {}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    let pairs = [
+        (State::Idle, Expected::Idle),
+        (State::Running(3, "x"), Expected::Running(3, "x")),
+        (
+            State::Failed {
+                code: -1,
+                flags: 0b101,
+            },
+            Expected::Failed {
+                code: -1,
+                flags: Binary(0b101),
+            },
+        ),
+        (State::Empty(), Expected::Empty()),
+    ];
+    for (state, expected) in &pairs {
+        assert_eq!(format!("{:?}", state), format!("{:?}", expected));
+        assert_eq!(format!("{:#?}", state), format!("{:#?}", expected));
+    }
+
+    struct Id;
+
+    impl Trait for Id {
+        type Value = u8;
+    }
+
+    assert_debug::<Wrapper<Id>>();
+    assert_debug::<Never>();
+    assert_eq!(format!("{:?}", Wrapper::<Id>::Value(7)), "Value(7)");
+}
@@ -0,0 +1,61 @@
+// Tuple structs and unit structs print the same way as with the standard
+// library's #[derive(Debug)], through DebugTuple and the bare type name
+// respectively. Positional fields accept the same #[debug = "..."] attribute as
+// named ones.
+//
+// Newtypes often exist only to give a plain value a distinct type, and
+// printing the wrapper adds nothing. With #[debug(transparent)] on a struct
+// with exactly one field, the impl forwards to that field:
+//
+//     impl Debug for UserId {
+//         fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+//             match self {
+//                 Self { 0: __self_0 } => Debug::fmt(__self_0, f),
+//             }
+//         }
+//     }
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+pub struct Point(i32, #[debug = "{:.1}"] f64);
+
+#[derive(CustomDebug)]
+pub struct Marker;
+
+#[derive(CustomDebug)]
+#[debug(transparent)]
+pub struct UserId(u64);
+
+#[derive(CustomDebug)]
+#[debug(transparent)]
+pub struct Mask(#[debug = "0b{:08b}"] u8);
+
+#[derive(CustomDebug)]
+#[debug(transparent)]
+pub struct Name<T> {
+    inner: T,
+}
+
+#[derive(CustomDebug)]
+pub struct Session {
+    user: UserId,
+    mask: Mask,
+}
+
+fn main() {
+    assert_eq!(format!("{:?}", Point(1, 2.0)), "Point(1, 2.0)");
+    assert_eq!(format!("{:?}", Marker), "Marker");
+    assert_eq!(format!("{:?}", UserId(42)), "42");
+    assert_eq!(format!("{:?}", Mask(5)), "0b00000101");
+    assert_eq!(format!("{:?}", Name { inner: "x" }), r#""x""#);
+
+    let session = Session {
+        user: UserId(7),
+        mask: Mask(1),
+    };
+    assert_eq!(
+        format!("{:?}", session),
+        "Session { user: 7, mask: 0b00000001 }",
+    );
+}
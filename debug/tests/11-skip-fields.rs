@@ -0,0 +1,73 @@
+// Not every field is worth printing. Support three field attributes:
+//
+//   - #[debug(skip)] leaves the field out entirely. Its type no longer
+//     contributes to the inferred bounds, so a skipped field of type T does
+//     not require T: Debug. The output ends with `..` through
+//     DebugStruct::finish_non_exhaustive to show that something was left out.
+//
+//   - #[debug(skip_if = "path::to::predicate")] leaves the field out whenever
+//     the predicate, called with a reference to the field, returns true. The
+//     output ends with `..` only if a field was actually left out.
+//
+//   - #[debug(rename = "...")] prints the field under a different name.
+
+use derive_debug::CustomDebug;
+use std::fmt::Debug;
+
+#[derive(CustomDebug)]
+pub struct Connection<C> {
+    #[debug(rename = "addr")]
+    address: &'static str,
+    #[debug(skip)]
+    cache: C,
+    retries: u8,
+}
+
+#[derive(CustomDebug)]
+pub struct Request {
+    method: &'static str,
+    #[debug(skip_if = "Option::is_none")]
+    body: Option<String>,
+    #[debug(skip_if = "Vec::is_empty")]
+    headers: Vec<&'static str>,
+}
+
+#[derive(CustomDebug)]
+pub struct Pair(u8, #[debug(skip)] u8);
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    struct NotDebug;
+
+    assert_debug::<Connection<NotDebug>>();
+
+    let conn = Connection {
+        address: "localhost",
+        cache: NotDebug,
+        retries: 3,
+    };
+    assert_eq!(
+        format!("{:?}", conn),
+        r#"Connection { addr: "localhost", retries: 3, .. }"#,
+    );
+
+    let request = Request {
+        method: "GET",
+        body: None,
+        headers: vec![],
+    };
+    assert_eq!(format!("{:?}", request), r#"Request { method: "GET", .. }"#);
+
+    let request = Request {
+        method: "POST",
+        body: Some("{}".to_owned()),
+        headers: vec!["Accept"],
+    };
+    assert_eq!(
+        format!("{:?}", request),
+        r#"Request { method: "POST", body: Some("{}"), headers: ["Accept"] }"#,
+    );
+
+    assert_eq!(format!("{:?}", Pair(1, 2)), "Pair(1, ..)");
+}
@@ -0,0 +1,57 @@
+// A format string only helps for types that already implement some formatting
+// trait. For everything else, #[debug(with = "path::to::fn")] names a function
+//
+//     fn(&FieldType, &mut fmt::Formatter) -> fmt::Result
+//
+// that renders the field in place of its Debug impl. Since the field's own
+// Debug impl is never used, no Debug bound is inferred from its type.
+
+use derive_debug::CustomDebug;
+use std::fmt::{self, Debug};
+
+// Foreign type without a Debug impl.
+pub struct Handle {
+    fd: i32,
+}
+
+fn fmt_handle(handle: &Handle, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "fd#{}", handle.fd)
+}
+
+fn fmt_callback(_: &fn(u32) -> u32, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str("<callback>")
+}
+
+fn fmt_opaque<T>(_: &T, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "<{}>", std::any::type_name::<T>())
+}
+
+#[derive(CustomDebug)]
+pub struct Watcher<T> {
+    #[debug(with = "fmt_handle")]
+    handle: Handle,
+    #[debug(with = "fmt_callback")]
+    callback: fn(u32) -> u32,
+    #[debug(with = "fmt_opaque")]
+    state: T,
+    events: u64,
+}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    struct NotDebug;
+
+    assert_debug::<Watcher<NotDebug>>();
+
+    let watcher = Watcher {
+        handle: Handle { fd: 3 },
+        callback: |n| n + 1,
+        state: 0u8,
+        events: 12,
+    };
+    assert_eq!(
+        format!("{:?}", watcher),
+        "Watcher { handle: fd#3, callback: <callback>, state: <u8>, events: 12 }",
+    );
+}
@@ -0,0 +1,71 @@
+// Credentials have a way of ending up in logs through derived Debug impls.
+// Fields holding secrets can be marked to be redacted:
+//
+//   - #[debug(redact)] prints `<redacted>` in place of the value.
+//
+//   - #[debug(redact = "len")] prints only the length, as in
+//     `<redacted len=12>`, which helps to spot empty or truncated secrets.
+//
+//   - #[debug(redact = "hash")] prints a short digest of the value, as in
+//     `<redacted #1b2e4f80>`, so that log lines carrying the same secret can be
+//     correlated without revealing it. The digest uses the field's Hash impl
+//     and is stable across runs and compiler versions.
+//
+// Redacted fields are not printed through Debug, so they don't contribute
+// Debug bounds; a hashed field of generic type gets a Hash bound instead.
+
+use derive_debug::CustomDebug;
+use std::fmt::Debug;
+
+#[derive(CustomDebug)]
+pub struct Credentials<K> {
+    user: &'static str,
+    #[debug(redact)]
+    password: String,
+    #[debug(redact = "len")]
+    token: Vec<u8>,
+    #[debug(redact = "hash")]
+    api_key: K,
+}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    #[derive(Hash)]
+    struct NotDebug;
+
+    assert_debug::<Credentials<NotDebug>>();
+
+    let creds = Credentials {
+        user: "admin",
+        password: "hunter2".to_owned(),
+        token: vec![0; 12],
+        api_key: "abc123",
+    };
+    let debug = format!("{:?}", creds);
+    assert!(!debug.contains("hunter2"));
+    assert!(!debug.contains("abc123"));
+    assert!(debug.starts_with(
+        r#"Credentials { user: "admin", password: <redacted>, token: <redacted len=12>, api_key: <redacted #"#
+    ));
+
+    let same = Credentials {
+        user: "guest",
+        password: String::new(),
+        token: Vec::new(),
+        api_key: "abc123",
+    };
+    let other = Credentials {
+        user: "guest",
+        password: String::new(),
+        token: Vec::new(),
+        api_key: "xyz789",
+    };
+    let digest = |creds: &Credentials<&str>| {
+        let debug = format!("{:?}", creds);
+        let start = debug.find("api_key: ").unwrap();
+        debug[start..].to_owned()
+    };
+    assert_eq!(digest(&creds), digest(&same));
+    assert_ne!(digest(&creds), digest(&other));
+}
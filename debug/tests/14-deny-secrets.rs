@@ -0,0 +1,20 @@
+// With #[debug(deny_secrets)] on the type, any field whose name contains
+// `password`, `token` or `secret` must be redacted or skipped, so that a newly
+// added credential field cannot silently end up in the Debug output. Every
+// offending field is reported.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+#[debug(deny_secrets)]
+pub struct Config {
+    user: String,
+    db_password: String,
+    #[debug(redact)]
+    api_token: String,
+    #[debug(skip)]
+    client_secret: String,
+    RefreshToken: String,
+}
+
+fn main() {}
@@ -0,0 +1,37 @@
+// Format strings in #[debug = "..."] are checked by the macro itself rather
+// than left to format_args!, whose errors would point into the generated impl
+// instead of at the attribute.
+//
+// The field is the only argument available to the format string. Every
+// placeholder must refer to it, either as `{}` or explicitly as `{0}`, and it
+// must be used at least once. Named arguments, a second `{}`, and width or
+// precision arguments referring to anything but the field are rejected.
+//
+// Where the compiler supports it, the error points at the offending part of
+// the string literal; otherwise it points at the whole literal.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+pub struct Field {
+    #[debug = "{:>8}"]
+    ok_align: u8,
+    #[debug = "{0:#x} ({0})"]
+    ok_positional: u8,
+    #[debug = "{{{:?}}}"]
+    ok_escaped: u8,
+    #[debug = "{} and {}"]
+    two: u8,
+    #[debug = "{name}"]
+    named: u8,
+    #[debug = "{:.*}"]
+    star: f32,
+    #[debug = "no placeholder"]
+    unused: u8,
+    #[debug = "0b{:08b"]
+    unclosed: u8,
+    #[debug = "{:z}"]
+    bad_trait: u8,
+}
+
+fn main() {}
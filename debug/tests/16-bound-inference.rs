@@ -0,0 +1,94 @@
+// More shapes of field types that the bound inference handles:
+//
+//   - Associated types nested anywhere inside other generic types, like
+//     Vec<Option<T::Value>>, get a `T::Value: Debug` bound.
+//
+//   - Fully qualified associated types like <T as Trait>::Value are bounded
+//     as written, again without requiring T: Debug.
+//
+//   - Type parameters declared ?Sized and used behind a reference or Box get
+//     the usual `T: Debug` bound, which does not imply Sized.
+//
+//   - Function pointers implement Debug whatever their signature, so type
+//     parameters appearing in one contribute no bound.
+//
+//   - A trait object mentioning a type parameter, like Box<dyn Source<T>>, is
+//     printable only if the trait has Debug as a supertrait, so the bound is
+//     placed on the trait object itself: `(dyn Source<T>): Debug`.
+//
+// Finally #[debug(bound = "")] disables inference without adding any bound of
+// its own, for types whose fields are all printable regardless of parameters.
+
+use derive_debug::CustomDebug;
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+pub trait Trait {
+    type Value;
+}
+
+pub trait Source<T>: Debug {}
+
+#[derive(CustomDebug)]
+pub struct Nested<T: Trait> {
+    values: Vec<Option<T::Value>>,
+    qualified: Option<<T as Trait>::Value>,
+}
+
+#[derive(CustomDebug)]
+pub struct Unsized<'a, T: ?Sized, U: ?Sized> {
+    reference: &'a T,
+    boxed: Box<U>,
+}
+
+#[derive(CustomDebug)]
+pub struct Callback<A, R> {
+    function: fn(A) -> R,
+}
+
+#[derive(CustomDebug)]
+pub struct Dynamic<'a, T> {
+    source: Box<dyn Source<T> + 'a>,
+}
+
+#[derive(CustomDebug)]
+#[debug(bound = "")]
+pub struct Tagged<T> {
+    id: u32,
+    marker: PhantomData<fn() -> T>,
+}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    struct NotDebug;
+
+    impl Trait for NotDebug {
+        type Value = u8;
+    }
+
+    #[derive(Debug)]
+    struct Numbers;
+
+    impl Source<NotDebug> for Numbers {}
+
+    assert_debug::<Nested<NotDebug>>();
+    assert_debug::<Unsized<str, [u8]>>();
+    assert_debug::<Callback<NotDebug, NotDebug>>();
+    assert_debug::<Dynamic<NotDebug>>();
+    assert_debug::<Tagged<NotDebug>>();
+
+    let unsized_ = Unsized::<str, [u8]> {
+        reference: "abc",
+        boxed: Box::new([1, 2]),
+    };
+    assert_eq!(
+        format!("{:?}", unsized_),
+        r#"Unsized { reference: "abc", boxed: [1, 2] }"#,
+    );
+
+    let dynamic = Dynamic::<NotDebug> {
+        source: Box::new(Numbers),
+    };
+    assert_eq!(format!("{:?}", dynamic), "Dynamic { source: Numbers }");
+}
@@ -0,0 +1,59 @@
+// The escape hatch from 08-escape-hatch.rs replaces every inferred bound of the
+// struct. Also accept #[debug(bound = "...")] on individual fields, which
+// replaces only the bounds inferred from that field's type while inference
+// keeps running for the other fields:
+//
+//     #[derive(CustomDebug)]
+//     pub struct Wrapper<T: Trait, U> {
+//         #[debug(bound = "T::Value: Debug")]
+//         field: Field<T>,
+//         normal: U,
+//     }
+//
+// expands to
+//
+//     impl<T: Trait, U> Debug for Wrapper<T, U>
+//     where
+//         U: Debug,
+//         T::Value: Debug,
+//     {...}
+
+use derive_debug::CustomDebug;
+use std::fmt::Debug;
+
+pub trait Trait {
+    type Value;
+}
+
+#[derive(CustomDebug)]
+pub struct Wrapper<T: Trait, U> {
+    #[debug(bound = "T::Value: Debug")]
+    field: Field<T>,
+    normal: U,
+}
+
+#[derive(CustomDebug)]
+pub struct Field<T: Trait> {
+    values: Vec<T::Value>,
+}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    struct Id;
+
+    impl Trait for Id {
+        type Value = u8;
+    }
+
+    assert_debug::<Wrapper<Id, u8>>();
+
+    let wrapper = Wrapper::<Id, _> {
+        field: Field { values: vec![1, 2] },
+        normal: "x",
+    };
+    assert_eq!(
+        format!("{:?}", wrapper),
+        r#"Wrapper { field: Field { values: [1, 2] }, normal: "x" }"#,
+    );
+}
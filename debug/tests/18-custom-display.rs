@@ -0,0 +1,83 @@
+// Alongside CustomDebug, provide a CustomDisplay derive for the user-facing
+// side of a type. The format string goes on the struct, or on each variant of
+// an enum, and refers to fields by name. Every placeholder is checked against
+// the fields at compile time, and only the fields the template mentions need
+// to implement the trait they are formatted with:
+//
+//     #[derive(CustomDisplay)]
+//     #[display("{name} (mask {bitmask:#b})")]
+//     pub struct Field<T> {
+//         name: T,
+//         bitmask: u8,
+//         notes: Vec<String>,
+//     }
+//
+// expands to
+//
+//     impl<T> Display for Field<T>
+//     where
+//         T: Display,
+//     {...}
+//
+// Tuple fields are referred to by index, or in order by `{}`.
+
+use derive_debug::CustomDisplay;
+use std::fmt::Display;
+
+#[derive(CustomDisplay)]
+#[display("{name} (mask {bitmask:#b})")]
+pub struct Field<T, U> {
+    name: T,
+    bitmask: u8,
+    notes: U,
+}
+
+#[derive(CustomDisplay)]
+#[display("{}.{:>2$}")]
+pub struct Version(u32, u32, #[allow(dead_code)] usize);
+
+#[derive(CustomDisplay)]
+pub enum Shape<T> {
+    #[display("circle of radius {radius}")]
+    Circle { radius: T },
+    #[display("{0}x{1} rectangle ({0:?} by {1:?})")]
+    Rectangle(T, T),
+    #[display("{{empty}}")]
+    Empty,
+}
+
+#[derive(CustomDisplay)]
+#[display("{value:.precision$}")]
+pub struct Rounded {
+    value: f64,
+    precision: usize,
+}
+
+fn assert_display<F: Display>() {}
+
+fn main() {
+    // No bound on U, which the template never mentions.
+    struct NotDisplay;
+    assert_display::<Field<&str, NotDisplay>>();
+
+    let field = Field {
+        name: "F",
+        bitmask: 0b00011100,
+        notes: NotDisplay,
+    };
+    assert_eq!(field.to_string(), "F (mask 0b11100)");
+
+    assert_eq!(Version(1, 2, 3).to_string(), "1.  2");
+
+    let circle = Shape::Circle { radius: 2 };
+    assert_eq!(circle.to_string(), "circle of radius 2");
+    let rectangle = Shape::Rectangle("a", "b");
+    assert_eq!(rectangle.to_string(), "axb rectangle (\"a\" by \"b\")");
+    assert_eq!(Shape::<u8>::Empty.to_string(), "{empty}");
+
+    let rounded = Rounded {
+        value: 1.23456,
+        precision: 2,
+    };
+    assert_eq!(rounded.to_string(), "1.23");
+}
@@ -0,0 +1,31 @@
+// Placeholders in a #[display("...")] template must name fields that exist.
+// A typo is reported at the template rather than as an unresolved name inside
+// the generated impl.
+
+use derive_debug::CustomDisplay;
+
+#[derive(CustomDisplay)]
+#[display("{name} (mask {bitmsk:#b})")]
+pub struct Field {
+    name: &'static str,
+    bitmask: u8,
+}
+
+#[derive(CustomDisplay)]
+#[display("{0} {2}")]
+pub struct Pair(u8, u8);
+
+#[derive(CustomDisplay)]
+#[display("{} {}")]
+pub struct Named {
+    value: u8,
+}
+
+#[derive(CustomDisplay)]
+pub enum Shape {
+    #[display("circle of radius {radius}")]
+    Circle { radius: u8 },
+    Empty,
+}
+
+fn main() {}
@@ -0,0 +1,82 @@
+// Printing a struct that holds a large buffer shouldn't flood the logs. Accept
+// #[debug(truncate = N)] on collection and string fields to print at most N
+// elements or characters, followed by how many were left out:
+//
+//     Packet { payload: [1, 2, 3, ... (4093 more)], note: "abcde"... (20 more) }
+//
+// This works for strings, slices, arrays, Vec, VecDeque and the standard sets
+// and maps.
+//
+// Byte buffers are more compact as hex. #[debug(bytes)] prints anything that
+// is AsRef<[u8]> as one run of hex digits, and can be combined with truncate
+// to limit the number of bytes shown:
+//
+//     Frame { header: [cafe], body: [00010203... (252 more)] }
+
+use derive_debug::CustomDebug;
+use std::collections::{BTreeMap, HashSet};
+
+#[derive(CustomDebug)]
+pub struct Packet<'a> {
+    #[debug(truncate = 3)]
+    payload: Vec<u32>,
+    #[debug(truncate = 5)]
+    note: String,
+    #[debug(truncate = 2)]
+    headers: BTreeMap<&'static str, &'static str>,
+    #[debug(truncate = 4)]
+    tags: &'a [&'static str],
+    #[debug(truncate = 10)]
+    short: &'a str,
+}
+
+#[derive(CustomDebug)]
+pub struct Frame<B> {
+    #[debug(bytes)]
+    header: [u8; 2],
+    #[debug(bytes, truncate = 4)]
+    body: B,
+}
+
+#[derive(CustomDebug)]
+pub struct Seen {
+    #[debug(truncate = 0)]
+    ids: HashSet<u8>,
+}
+
+fn main() {
+    let mut headers = BTreeMap::new();
+    headers.insert("host", "example.com");
+    headers.insert("accept", "*/*");
+    headers.insert("user-agent", "test");
+
+    let packet = Packet {
+        payload: (1..=4096).collect(),
+        note: "abcdefghijklmnopqrstuvwxy".to_owned(),
+        headers,
+        tags: &["a", "b"],
+        short: "ünïcödé",
+    };
+    assert_eq!(
+        format!("{:?}", packet),
+        concat!(
+            r#"Packet { payload: [1, 2, 3, ... (4093 more)], note: "abcde"... (20 more), "#,
+            r#"headers: {"accept": "*/*", "host": "example.com", ... (1 more)}, "#,
+            r#"tags: ["a", "b"], short: "ünïcödé" }"#,
+        ),
+    );
+
+    let frame = Frame {
+        header: [0xca, 0xfe],
+        body: (0..=255).collect::<Vec<u8>>(),
+    };
+    assert_eq!(
+        format!("{:?}", frame),
+        "Frame { header: [cafe], body: [00010203... (252 more)] }",
+    );
+
+    let seen = Seen {
+        ids: vec![1, 2].into_iter().collect(),
+    };
+    assert_eq!(format!("{:?}", seen), "Seen { ids: {... (2 more)} }");
+}
@@ -0,0 +1,85 @@
+// Config types are often composed of small sub-structs, and the nested braces
+// make their Debug output hard to scan. Accept #[debug(flatten)] on a field
+// whose type also derives CustomDebug to print its fields inline in the
+// parent, optionally with a prefix on their names:
+//
+//     #[derive(CustomDebug)]
+//     pub struct Config {
+//         name: String,
+//         #[debug(flatten, prefix = "conn.")]
+//         connection: Connection,
+//     }
+//
+//     Config { name: "api", conn.host: "localhost", conn.port: 8080 }
+//
+// Flattening nests, with prefixes accumulating along the way, and fields left
+// out of a flattened struct still end the parent's output with `..`.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+pub struct Config {
+    name: &'static str,
+    #[debug(flatten, prefix = "conn.")]
+    connection: Connection,
+    #[debug(flatten)]
+    limits: Limits,
+}
+
+#[derive(CustomDebug)]
+pub struct Connection {
+    host: &'static str,
+    port: u16,
+    #[debug(flatten, prefix = "tls.")]
+    tls: Tls<bool>,
+}
+
+#[derive(CustomDebug)]
+pub struct Tls<T> {
+    enabled: T,
+    #[debug(skip)]
+    key: Vec<u8>,
+}
+
+#[derive(CustomDebug)]
+pub struct Limits {
+    #[debug(rename = "max_conns")]
+    max_connections: u32,
+}
+
+#[derive(CustomDebug)]
+pub enum Source {
+    Inline {
+        #[debug(flatten)]
+        limits: Limits,
+    },
+}
+
+fn main() {
+    let config = Config {
+        name: "api",
+        connection: Connection {
+            host: "localhost",
+            port: 8080,
+            tls: Tls {
+                enabled: true,
+                key: Vec::new(),
+            },
+        },
+        limits: Limits {
+            max_connections: 16,
+        },
+    };
+    assert_eq!(
+        format!("{:?}", config),
+        concat!(
+            r#"Config { name: "api", conn.host: "localhost", conn.port: 8080, "#,
+            r#"conn.tls.enabled: true, max_conns: 16, .. }"#,
+        ),
+    );
+
+    let source = Source::Inline {
+        limits: Limits { max_connections: 1 },
+    };
+    assert_eq!(format!("{:?}", source), "Inline { max_conns: 1 }");
+}
@@ -0,0 +1,64 @@
+// Standard hash maps iterate in a random order, which makes Debug output
+// unusable in snapshot tests. Accept #[debug(sorted)] on HashMap and HashSet
+// fields to print their entries ordered by key, and #[debug(sorted)] on the
+// struct to do so for every field whose type is written as a HashMap or
+// HashSet.
+//
+// A sorted field of generic key type requires the key to be Ord:
+//
+//     impl<K> Debug for Index<K>
+//     where
+//         K: Debug,
+//         K: Ord,
+//     {...}
+
+use derive_debug::CustomDebug;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+
+#[derive(CustomDebug)]
+pub struct Index<K> {
+    #[debug(sorted)]
+    entries: HashMap<K, usize>,
+    unsorted: Vec<&'static str>,
+}
+
+#[derive(CustomDebug)]
+#[debug(sorted)]
+pub struct Snapshot {
+    names: HashSet<&'static str>,
+    counts: std::collections::HashMap<u8, char>,
+    list: Vec<u8>,
+}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    assert_debug::<Index<u8>>();
+
+    let index = Index {
+        entries: (0..20).map(|i| (i, i * 2)).collect(),
+        unsorted: vec!["b", "a"],
+    };
+    let expected: Vec<String> = (0..20).map(|i| format!("{}: {}", i, i * 2)).collect();
+    assert_eq!(
+        format!("{:?}", index),
+        format!(
+            r#"Index {{ entries: {{{}}}, unsorted: ["b", "a"] }}"#,
+            expected.join(", "),
+        ),
+    );
+
+    let snapshot = Snapshot {
+        names: vec!["carol", "alice", "bob"].into_iter().collect(),
+        counts: vec![(3, 'c'), (1, 'a'), (2, 'b')].into_iter().collect(),
+        list: vec![3, 1, 2],
+    };
+    assert_eq!(
+        format!("{:?}", snapshot),
+        concat!(
+            r#"Snapshot { names: {"alice", "bob", "carol"}, "#,
+            r#"counts: {1: 'a', 2: 'b', 3: 'c'}, list: [3, 1, 2] }"#,
+        ),
+    );
+}
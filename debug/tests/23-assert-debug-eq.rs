@@ -0,0 +1,91 @@
+// When assert_eq! fails on two large structs, the message is two walls of text
+// that have to be compared by eye. Provide assert_debug_eq!(left, right), which
+// compares values by their Debug output and, for structs deriving
+// CustomDebug, reports only the fields that differ:
+//
+//     assertion `left == right` failed (Debug output differs)
+//       conn.port:
+//          left: 8080
+//         right: 8081
+//
+// Fields whose types derive CustomDebug are compared recursively, with their
+// path joined by `.` (or by the prefix of a flattened field). Fields are
+// compared as printed, so skipped fields are ignored and redacted fields never
+// reveal their values.
+
+use derive_debug::{assert_debug_eq, CustomDebug};
+use std::panic;
+
+#[derive(CustomDebug)]
+pub struct Config {
+    name: &'static str,
+    conn: Connection,
+    #[debug(flatten, prefix = "limit.")]
+    limits: Limits,
+    #[debug(redact)]
+    password: String,
+    #[debug(skip)]
+    generation: u32,
+}
+
+#[derive(CustomDebug)]
+pub struct Connection {
+    host: &'static str,
+    port: u16,
+}
+
+#[derive(CustomDebug)]
+pub struct Limits {
+    conns: u32,
+}
+
+fn config(port: u16, conns: u32, password: &str, generation: u32) -> Config {
+    Config {
+        name: "api",
+        conn: Connection {
+            host: "localhost",
+            port,
+        },
+        limits: Limits { conns },
+        password: password.to_owned(),
+        generation,
+    }
+}
+
+fn panic_message(f: impl FnOnce() + panic::UnwindSafe) -> String {
+    let payload = panic::catch_unwind(f).unwrap_err();
+    match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => (*payload.downcast::<&str>().unwrap()).to_owned(),
+    }
+}
+
+fn main() {
+    panic::set_hook(Box::new(|_| {}));
+
+    assert_debug_eq!(config(80, 1, "a", 1), config(80, 1, "b", 2));
+
+    let message = panic_message(|| {
+        assert_debug_eq!(config(8080, 4, "a", 1), config(8081, 5, "b", 1));
+    });
+    assert_eq!(
+        message,
+        concat!(
+            "assertion `left == right` failed (Debug output differs)\n",
+            "  conn.port:\n",
+            "     left: 8080\n",
+            "    right: 8081\n",
+            "  limit.conns:\n",
+            "     left: 4\n",
+            "    right: 5",
+        ),
+    );
+
+    let message = panic_message(|| {
+        assert_debug_eq!(Some(1), None::<i32>, "lookup {}", "failed");
+    });
+    assert_eq!(
+        message,
+        "assertion `left == right` failed: lookup failed\n   left: Some(1)\n  right: None",
+    );
+}
@@ -0,0 +1,75 @@
+// Fields of a #[repr(packed)] struct may not be aligned, so they can't be
+// borrowed for printing. The derive detects packed structs and copies each
+// printed field into a local first, which requires the fields to be Copy; a
+// generic field gets a Copy bound alongside its Debug bound.
+//
+// Unions don't say which of their fields is valid, so CustomDebug prints them
+// opaquely as `Name { .. }`. Alternatively a function can be given for the
+// whole type with #[debug(with = "...")], which works for structs and enums
+// too:
+//
+//     #[derive(CustomDebug)]
+//     #[debug(with = "fmt_word")]
+//     pub union Word {
+//         int: u32,
+//         bytes: [u8; 4],
+//     }
+
+use derive_debug::CustomDebug;
+use std::fmt::{self, Debug};
+
+#[derive(CustomDebug)]
+#[repr(C, packed)]
+pub struct Header<T> {
+    tag: u8,
+    #[debug = "{:#06x}"]
+    length: u32,
+    checksum: T,
+}
+
+#[derive(CustomDebug)]
+#[repr(packed(2))]
+pub struct Pair(u8, u64);
+
+#[derive(CustomDebug)]
+pub union Raw {
+    int: u32,
+    float: f32,
+}
+
+#[derive(CustomDebug)]
+#[debug(with = "fmt_word")]
+pub union Word {
+    int: u32,
+    bytes: [u8; 4],
+}
+
+fn fmt_word(word: &Word, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "Word({:#010x})", unsafe { word.int })
+}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    assert_debug::<Header<u16>>();
+
+    let header = Header {
+        tag: 7,
+        length: 1024,
+        checksum: 0xbeefu16,
+    };
+    assert_eq!(
+        format!("{:?}", header),
+        "Header { tag: 7, length: 0x0400, checksum: 48879 }",
+    );
+
+    assert_eq!(format!("{:?}", Pair(1, 2)), "Pair(1, 2)");
+
+    let raw = Raw { int: 1 };
+    assert_eq!(format!("{:?}", raw), "Raw { .. }");
+    let _ = unsafe { raw.float };
+
+    let word = Word { int: 0xdeadbeef };
+    assert_eq!(format!("{:?}", word), "Word(0xdeadbeef)");
+    let _ = unsafe { word.bytes };
+}
@@ -0,0 +1,15 @@
+// Fields of a packed struct are copied out before printing, so each printed
+// field must be Copy. Other fields are reported with a hint to skip them.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+#[repr(packed)]
+pub struct Message {
+    id: u32,
+    body: String,
+    #[debug(skip)]
+    extra: Vec<u8>,
+}
+
+fn main() {}
@@ -0,0 +1,49 @@
+// Types sometimes want to show information derived from their fields, like a
+// length, checksum or summary of their state, without storing it. Accept
+// #[debug(extra(name = "...", expr = "..."))] on a struct with named fields,
+// any number of times, to append computed entries after the fields. The
+// expression may refer to `self`.
+//
+//     #[derive(CustomDebug)]
+//     #[debug(extra(name = "len", expr = "self.values.len()"))]
+//     pub struct Buffer {
+//         values: Vec<u8>,
+//     }
+//
+//     Buffer { values: [1, 2, 3], len: 3 }
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+#[debug(extra(name = "len", expr = "self.values.len()"))]
+#[debug(extra(name = "sum", expr = "self.values.iter().map(|&v| v as u32).sum::<u32>()"))]
+pub struct Buffer {
+    #[debug(truncate = 2)]
+    values: Vec<u8>,
+}
+
+#[derive(CustomDebug)]
+pub struct Stream {
+    id: u32,
+    #[debug(flatten, prefix = "buf.")]
+    buffer: Buffer,
+}
+
+fn main() {
+    let buffer = Buffer {
+        values: vec![1, 2, 3],
+    };
+    assert_eq!(
+        format!("{:?}", buffer),
+        "Buffer { values: [1, 2, ... (1 more)], len: 3, sum: 6 }",
+    );
+
+    let stream = Stream {
+        id: 1,
+        buffer: Buffer { values: vec![] },
+    };
+    assert_eq!(
+        format!("{:?}", stream),
+        "Stream { id: 1, buf.values: [], buf.len: 0, buf.sum: 0 }",
+    );
+}
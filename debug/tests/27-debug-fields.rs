@@ -0,0 +1,69 @@
+// Structured loggers want key/value pairs rather than one formatted string.
+// Alongside the Debug impl, generate
+//
+//     pub fn debug_fields(&self) -> impl Iterator<Item = (&'static str, &dyn Debug)>
+//
+// yielding the fields exactly as the Debug impl prints them: skipped fields
+// are left out, renamed fields use their new name, and each value is
+// formatted with the field's format string, redaction and so on. Tuple fields
+// are named by their index. For enums, the fields of the current variant are
+// yielded.
+
+use derive_debug::CustomDebug;
+use std::fmt::Debug;
+
+#[derive(CustomDebug)]
+pub struct Request<'a, T> {
+    method: &'a str,
+    #[debug = "0x{:04x}"]
+    flags: u16,
+    #[debug(rename = "user")]
+    user_id: T,
+    #[debug(redact)]
+    token: String,
+    #[debug(skip)]
+    internal: u8,
+    #[debug(skip_if = "Option::is_none")]
+    parent: Option<u32>,
+}
+
+#[derive(CustomDebug)]
+pub struct Point(i32, #[debug = "{}px"] i32);
+
+#[derive(CustomDebug)]
+pub enum Event {
+    Click { x: i32, y: i32 },
+    Key(char),
+    Idle,
+}
+
+fn pairs<'a>(fields: impl Iterator<Item = (&'static str, &'a dyn Debug)>) -> Vec<String> {
+    fields
+        .map(|(name, value)| format!("{}={:?}", name, value))
+        .collect()
+}
+
+fn main() {
+    let request = Request {
+        method: "GET",
+        flags: 10,
+        user_id: 7u64,
+        token: "secret".to_owned(),
+        internal: 0,
+        parent: None,
+    };
+    assert_eq!(
+        pairs(request.debug_fields()),
+        ["method=\"GET\"", "flags=0x000a", "user=7", "token=<redacted>"],
+    );
+    let _ = request.internal;
+
+    assert_eq!(pairs(Point(1, 2).debug_fields()), ["0=1", "1=2px"]);
+
+    assert_eq!(
+        pairs(Event::Click { x: 1, y: 2 }.debug_fields()),
+        ["x=1", "y=2"],
+    );
+    assert_eq!(pairs(Event::Key('k').debug_fields()), ["0='k'"]);
+    assert_eq!(pairs(Event::Idle.debug_fields()), Vec::<String>::new());
+}
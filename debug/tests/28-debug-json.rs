@@ -0,0 +1,93 @@
+// Log pipelines ingest JSON, which Debug output is not. With #[debug(json)],
+// also generate
+//
+//     pub fn to_debug_json(&self) -> String
+//
+// rendering the value as valid JSON. Numbers, bools, strings and chars, and
+// options of them, are written natively; any other field is written as a
+// string holding its Debug output. Fields are written as the Debug impl
+// prints them, so format strings, redaction, skipping and renaming all apply.
+//
+// Structs with named fields become objects and tuple structs become arrays.
+// An enum variant with fields is wrapped in an object keyed by the variant
+// name, and a unit variant is written as just its name.
+
+use derive_debug::CustomDebug;
+use std::time::Duration;
+
+#[derive(CustomDebug)]
+#[debug(json)]
+pub struct Request<'a> {
+    method: &'a str,
+    path: String,
+    #[debug = "0x{:04x}"]
+    flags: u16,
+    #[debug(rename = "user")]
+    user_id: u64,
+    #[debug(redact)]
+    token: String,
+    #[debug(skip)]
+    internal: u8,
+    #[debug(skip_if = "Option::is_none")]
+    parent: Option<u32>,
+    retry: Option<u8>,
+    ratio: f64,
+    secure: bool,
+    timeout: Duration,
+}
+
+#[derive(CustomDebug)]
+#[debug(json)]
+pub struct Point(i32, #[debug = "{}px"] i32);
+
+#[derive(CustomDebug)]
+#[debug(json)]
+pub enum Event {
+    Click { x: i32, y: i32 },
+    Key(char),
+    Idle,
+}
+
+#[derive(CustomDebug)]
+#[debug(json, transparent)]
+pub struct UserId(u64);
+
+fn main() {
+    let request = Request {
+        method: "GET",
+        path: "/a \"quoted\"\\path\n\u{1}".to_owned(),
+        flags: 0x1f,
+        user_id: 7,
+        token: "hunter2".to_owned(),
+        internal: 0,
+        parent: None,
+        retry: None,
+        ratio: 0.5,
+        secure: true,
+        timeout: Duration::from_millis(1500),
+    };
+    assert_eq!(
+        request.to_debug_json(),
+        r#"{"method":"GET","path":"/a \"quoted\"\\path\n\u0001","flags":"0x001f","user":7,"token":"<redacted>","retry":null,"ratio":0.5,"secure":true,"timeout":"1.5s"}"#,
+    );
+
+    let request = Request {
+        parent: Some(1),
+        retry: Some(3),
+        ratio: f64::NAN,
+        ..request
+    };
+    let json = request.to_debug_json();
+    assert!(json.contains(r#""parent":1,"retry":3,"ratio":null"#));
+
+    assert_eq!(Point(1, 2).to_debug_json(), r#"[1,"2px"]"#);
+
+    assert_eq!(
+        Event::Click { x: 1, y: -2 }.to_debug_json(),
+        r#"{"Click":{"x":1,"y":-2}}"#,
+    );
+    assert_eq!(Event::Key('"').to_debug_json(), r#"{"Key":["\""]}"#);
+    assert_eq!(Event::Idle.to_debug_json(), r#""Idle""#);
+
+    assert_eq!(UserId(42).to_debug_json(), "42");
+}
@@ -0,0 +1,102 @@
+// Deeply nested values, like a long linked list or a large AST, print
+// megabytes of output, and a cyclic structure overflows the stack. Support a
+// struct-level attribute
+//
+//     #[debug(max_depth = N)]
+//
+// after which a value of the type nested inside N others of the same type
+// prints as `...` instead. Track the depth in a thread-local counter inside
+// the generated impl.
+//
+// Mutually recursive types, as in 06-bound-trouble, are limited as soon as
+// either of them has a max_depth.
+//
+// Resources:
+//
+//   - The `thread_local!` macro:
+//     https://doc.rust-lang.org/std/macro.thread_local.html
+
+use derive_debug::CustomDebug;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[derive(CustomDebug)]
+#[debug(max_depth = 3)]
+pub struct List {
+    value: u8,
+    next: Option<Box<List>>,
+}
+
+#[derive(CustomDebug)]
+#[debug(max_depth = 2)]
+pub struct One<T> {
+    value: T,
+    two: Option<Box<Two<T>>>,
+}
+
+#[derive(CustomDebug)]
+pub struct Two<T> {
+    one: Box<One<T>>,
+}
+
+#[derive(CustomDebug)]
+#[debug(max_depth = 2)]
+pub struct Node {
+    name: &'static str,
+    #[debug(skip_if = "Option::is_none")]
+    parent: Option<Rc<RefCell<Node>>>,
+}
+
+fn list(values: &[u8]) -> Option<Box<List>> {
+    values.split_first().map(|(&value, rest)| {
+        Box::new(List {
+            value,
+            next: list(rest),
+        })
+    })
+}
+
+fn main() {
+    let short = list(&[1, 2]).unwrap();
+    assert_eq!(
+        format!("{:?}", short),
+        "List { value: 1, next: Some(List { value: 2, next: None }) }",
+    );
+
+    let long = list(&[1, 2, 3, 4, 5]).unwrap();
+    assert_eq!(
+        format!("{:?}", long),
+        "List { value: 1, next: Some(List { value: 2, next: Some(List { value: 3, next: Some(...) }) }) }",
+    );
+    // The counter is back at zero afterwards.
+    assert_eq!(format!("{:?}", long), format!("{:?}", long));
+
+    let one = One {
+        value: 1,
+        two: Some(Box::new(Two {
+            one: Box::new(One {
+                value: 2,
+                two: Some(Box::new(Two {
+                    one: Box::new(One {
+                        value: 3,
+                        two: None,
+                    }),
+                })),
+            }),
+        })),
+    };
+    assert_eq!(
+        format!("{:?}", one),
+        "One { value: 1, two: Some(Two { one: One { value: 2, two: Some(Two { one: ... }) } }) }",
+    );
+
+    // A cycle through a shared pointer.
+    let node = Rc::new(RefCell::new(Node {
+        name: "root",
+        parent: None,
+    }));
+    node.borrow_mut().parent = Some(Rc::clone(&node));
+    let debug = format!("{:?}", node);
+    assert!(debug.contains("Node { name: \"root\", parent: Some(RefCell { value: ... }) }"));
+    node.borrow_mut().parent = None;
+}
@@ -0,0 +1,117 @@
+// Structs carrying a cache or a timestamp need equality and hashing that
+// ignore those fields. Alongside CustomDebug, provide
+//
+//     #[derive(CustomPartialEq, CustomHash)]
+//
+// which compare and hash every field like the standard derives, except that
+// #[eq(skip)] and #[hash(skip)] leave a field out, and #[eq(with = "...")] or
+// #[hash(with = "...")] name a function to use in place of the field's own
+// impl:
+//
+//     fn eq_with(a: &T, b: &T) -> bool
+//     fn hash_with<H: Hasher>(value: &T, state: &mut H)
+//
+// Bounds are inferred the same way as for CustomDebug, so a PhantomData<T>
+// field places no bound on T, and an associated type like T::Value is bounded
+// itself.
+//
+// Values that are equal must hash equally, so a field skipped by #[eq(skip)]
+// must be skipped by #[hash(skip)] as well, and one compared by #[eq(with)]
+// needs #[hash(with)] or #[hash(skip)]. CustomHash rejects fields that don't.
+
+use derive_debug::{CustomHash, CustomPartialEq};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+
+pub trait Trait {
+    type Value;
+}
+
+#[derive(CustomPartialEq, CustomHash)]
+pub struct Entry<T: Trait, U> {
+    key: String,
+    #[eq(with = "eq_ignore_case")]
+    #[hash(with = "hash_ignore_case")]
+    label: String,
+    value: T::Value,
+    #[eq(skip)]
+    #[hash(skip)]
+    cached_len: usize,
+    marker: PhantomData<U>,
+}
+
+#[derive(CustomPartialEq, CustomHash)]
+pub enum Shape {
+    Circle(u32),
+    Square(u32),
+    Named {
+        name: &'static str,
+        #[eq(skip)]
+        #[hash(skip)]
+        touched: u64,
+    },
+    Empty,
+}
+
+fn eq_ignore_case(a: &str, b: &str) -> bool {
+    a.eq_ignore_ascii_case(b)
+}
+
+fn hash_ignore_case<H: Hasher>(value: &str, state: &mut H) {
+    value.to_ascii_lowercase().hash(state);
+}
+
+fn hash(value: &impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+struct Id;
+
+impl Trait for Id {
+    type Value = u8;
+}
+
+// Not PartialEq or Hash.
+struct NotComparable;
+
+fn main() {
+    let a = Entry::<Id, NotComparable> {
+        key: "k".to_owned(),
+        label: "Label".to_owned(),
+        value: 1,
+        cached_len: 0,
+        marker: PhantomData,
+    };
+    let b = Entry::<Id, NotComparable> {
+        key: "k".to_owned(),
+        label: "LABEL".to_owned(),
+        value: 1,
+        cached_len: 99,
+        marker: PhantomData,
+    };
+    assert!(a == b);
+    assert_eq!(hash(&a), hash(&b));
+
+    let c = Entry::<Id, NotComparable> { value: 2, ..b };
+    assert!(a != c);
+
+    assert!(Shape::Circle(1) == Shape::Circle(1));
+    assert!(Shape::Circle(1) != Shape::Circle(2));
+    assert!(Shape::Circle(1) != Shape::Square(1));
+    assert_ne!(hash(&Shape::Circle(1)), hash(&Shape::Square(1)));
+    assert!(Shape::Empty == Shape::Empty);
+
+    let x = Shape::Named {
+        name: "x",
+        touched: 1,
+    };
+    let y = Shape::Named {
+        name: "x",
+        touched: 2,
+    };
+    assert!(x == y);
+    assert_eq!(hash(&x), hash(&y));
+}
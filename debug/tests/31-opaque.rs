@@ -0,0 +1,75 @@
+// Library types shouldn't expose their internals through Debug, but writing
+// the impl by hand is still tedious. Support a struct-level attribute
+//
+//     #[debug(opaque)]
+//
+// which prints the value as `TypeName { .. }`, together with a field-level
+// #[debug(show)] whitelisting the fields that are printed anyway:
+//
+//     Client { endpoint: "https://example.com", .. }
+//
+// Hidden fields are treated exactly like skipped fields, so they place no
+// bounds on the impl and are left out of debug_fields as well. On an enum,
+// each variant prints its name and the fields it shows.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+#[debug(opaque)]
+pub struct Handle {
+    fd: i32,
+    buffer: Vec<u8>,
+}
+
+#[derive(CustomDebug)]
+#[debug(opaque)]
+pub struct Client<T> {
+    #[debug(show)]
+    endpoint: &'static str,
+    #[debug(show, rename = "retries")]
+    max_retries: u8,
+    // Not Debug, and not required to be.
+    state: T,
+}
+
+#[derive(CustomDebug)]
+#[debug(opaque)]
+pub enum Connection {
+    Open {
+        #[debug(show)]
+        peer: &'static str,
+        socket: i32,
+    },
+    Pending(u32),
+    Closed,
+}
+
+struct NotDebug;
+
+fn main() {
+    let handle = Handle {
+        fd: 3,
+        buffer: vec![1, 2, 3],
+    };
+    assert_eq!(format!("{:?}", handle), "Handle { .. }");
+
+    let client = Client {
+        endpoint: "https://example.com",
+        max_retries: 3,
+        state: NotDebug,
+    };
+    assert_eq!(
+        format!("{:?}", client),
+        "Client { endpoint: \"https://example.com\", retries: 3, .. }",
+    );
+    let names: Vec<_> = client.debug_fields().map(|(name, _)| name).collect();
+    assert_eq!(names, ["endpoint", "retries"]);
+
+    let open = Connection::Open {
+        peer: "10.0.0.1",
+        socket: 7,
+    };
+    assert_eq!(format!("{:?}", open), "Open { peer: \"10.0.0.1\", .. }");
+    assert_eq!(format!("{:?}", Connection::Pending(1)), "Pending(..)");
+    assert_eq!(format!("{:?}", Connection::Closed), "Closed");
+}
@@ -0,0 +1,14 @@
+// #[debug(show)] only means something on a #[debug(opaque)] type, where every
+// other field is hidden. Elsewhere it is most likely a leftover from removing
+// `opaque`, and is reported rather than ignored.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+pub struct Client {
+    #[debug(show)]
+    endpoint: &'static str,
+    token: String,
+}
+
+fn main() {}
@@ -0,0 +1,22 @@
+// Values that are equal must hash equally. A field left out of the comparison
+// by #[eq(skip)], or compared by a function of its own with #[eq(with =
+// "...")], would break that if CustomHash still hashed it by its Hash impl,
+// so CustomHash reports the field unless it has a matching #[hash(skip)] or
+// #[hash(with = "...")].
+
+use derive_debug::{CustomHash, CustomPartialEq};
+
+#[derive(CustomPartialEq, CustomHash)]
+pub struct Entry {
+    key: String,
+    #[eq(skip)]
+    cached_len: usize,
+    #[eq(with = "eq_ignore_case")]
+    label: String,
+}
+
+fn eq_ignore_case(a: &str, b: &str) -> bool {
+    a.eq_ignore_ascii_case(b)
+}
+
+fn main() {}
@@ -0,0 +1,68 @@
+// A field's type may refer to the containing type as `Self`, as in a linked
+// list. debug_fields must yield such a field, when it has a format string or
+// `with` function, with `Self` still meaning the type deriving CustomDebug
+// and not anything generated along the way.
+
+use derive_debug::CustomDebug;
+use std::fmt::{self, Debug};
+
+#[derive(CustomDebug)]
+pub struct List {
+    value: u64,
+    extra: u64,
+    #[debug = "next={:?}"]
+    next: Option<Box<Self>>,
+}
+
+#[derive(CustomDebug)]
+pub enum Tree<T> {
+    Leaf(T),
+    Node {
+        #[debug(with = "children")]
+        left: Option<Box<Self>>,
+        #[debug = "{:?}!"]
+        right: Option<Box<Self>>,
+    },
+}
+
+fn children<T: Debug>(child: &Option<Box<Tree<T>>>, f: &mut fmt::Formatter) -> fmt::Result {
+    match child {
+        Some(child) => write!(f, "<{:?}>", child),
+        None => f.write_str("<>"),
+    }
+}
+
+fn pairs<'a>(fields: impl Iterator<Item = (&'static str, &'a dyn Debug)>) -> Vec<String> {
+    fields
+        .map(|(name, value)| format!("{}: {:?}", name, value))
+        .collect()
+}
+
+fn main() {
+    let list = List {
+        value: 1,
+        extra: 2,
+        next: Some(Box::new(List {
+            value: 3,
+            extra: 4,
+            next: None,
+        })),
+    };
+    assert_eq!(
+        pairs(list.debug_fields()),
+        [
+            "value: 1",
+            "extra: 2",
+            "next: next=Some(List { value: 3, extra: 4, next: next=None })",
+        ],
+    );
+
+    let tree = Tree::Node {
+        left: Some(Box::new(Tree::Leaf(1))),
+        right: None,
+    };
+    assert_eq!(
+        pairs(tree.debug_fields()),
+        ["left: <Leaf(1)>", "right: None!"],
+    );
+}
@@ -1,12 +1,38 @@
 #[test]
 fn tests() {
     let t = trybuild::TestCases::new();
-    //t.pass("tests/01-parse.rs");
-    //t.pass("tests/02-impl-debug.rs");
-    //t.pass("tests/03-custom-format.rs");
-    //t.pass("tests/04-type-parameter.rs");
-    //t.pass("tests/05-phantom-data.rs");
-    //t.pass("tests/06-bound-trouble.rs");
-    //t.pass("tests/07-associated-type.rs");
-    //t.pass("tests/08-escape-hatch.rs");
+    t.pass("tests/01-parse.rs");
+    t.pass("tests/02-impl-debug.rs");
+    t.pass("tests/03-custom-format.rs");
+    t.pass("tests/04-type-parameter.rs");
+    t.pass("tests/05-phantom-data.rs");
+    t.pass("tests/06-bound-trouble.rs");
+    t.pass("tests/07-associated-type.rs");
+    t.pass("tests/08-escape-hatch.rs");
+    t.pass("tests/09-enum.rs");
+    t.pass("tests/10-tuple-struct.rs");
+    t.pass("tests/11-skip-fields.rs");
+    t.pass("tests/12-debug-with.rs");
+    t.pass("tests/13-redact.rs");
+    t.compile_fail("tests/14-deny-secrets.rs");
+    t.compile_fail("tests/15-format-string.rs");
+    t.pass("tests/16-bound-inference.rs");
+    t.pass("tests/17-field-bound.rs");
+    t.pass("tests/18-custom-display.rs");
+    t.compile_fail("tests/19-display-unknown-field.rs");
+    t.pass("tests/20-truncate.rs");
+    t.pass("tests/21-flatten.rs");
+    t.pass("tests/22-sorted.rs");
+    t.pass("tests/23-assert-debug-eq.rs");
+    t.pass("tests/24-packed-union.rs");
+    t.compile_fail("tests/25-packed-not-copy.rs");
+    t.pass("tests/26-extra-entries.rs");
+    t.pass("tests/27-debug-fields.rs");
+    t.pass("tests/28-debug-json.rs");
+    t.pass("tests/29-max-depth.rs");
+    t.pass("tests/30-partial-eq-hash.rs");
+    t.pass("tests/31-opaque.rs");
+    t.compile_fail("tests/32-show-without-opaque.rs");
+    t.compile_fail("tests/33-eq-skip-not-hashed.rs");
+    t.pass("tests/34-debug-fields-self.rs");
 }